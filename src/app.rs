@@ -1,6 +1,82 @@
-use crate::{cache::Cache, fetch, highlight, prettify};
+use crate::{
+    cache::Cache,
+    config::{PaneLayout, PaneSize},
+    fetch::{self, NoReadme},
+    fuzzy::fuzzy_match,
+    highlight,
+    input::{self, Edit, History},
+    keymap::{Keymap, Mode},
+    math::{mark_display_math, render_math},
+    mouse::Click,
+    plain_lines, prettify,
+    preview::Preview,
+    search::{CourseHit, CourseIndex, IndexedLesson, ReadmeMatch, find_matches},
+    theme::Theme,
+    toc::{self, TocEntry},
+};
 use anyhow::anyhow;
-use ratatui::widgets::ListState;
+use ratatui::{layout::Rect, widgets::ListState};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+const MAX_PARALLEL_FETCHES: usize = 8;
+// How often to check on a course being indexed
+const INDEXING_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Columns a side pane grows or shrinks by, and the narrowest it can be resized to
+const PANE_STEP: i32 = 4;
+pub const MIN_PANE_WIDTH: u16 = 12;
+
+// What has the keyboard over the panes, if anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overlay {
+    #[default]
+    None,
+    Search, // Typing a search or filter
+    CourseSearch,
+    LessonFinder,
+    Toc,
+    Help,
+}
+
+// A course being indexed in the background. Dropping it cancels it; the
+// fetches already sent finish, but their results are ignored.
+#[derive(Debug)]
+pub struct Indexing {
+    course_uuid: String,
+    loading: Receiver<Result<Indexed, anyhow::Error>>,
+}
+
+// What indexing brings back, for the cache
+#[derive(Debug)]
+struct Indexed {
+    index: CourseIndex,
+    lesson_count: usize,
+    readmes: Vec<(String, String, String)>, // Lesson ID, raw and highlighted readme
+    plain_readmes: Vec<(String, Vec<String>)>, // Lesson ID, plain lines
+}
+
+// A lesson to index, with whatever of its readme is cached already
+struct IndexEntry {
+    chapter_no: usize,
+    lesson_no: usize,
+    title: String,
+    lesson_id: String,
+    raw: Option<String>,
+    lines: Option<Vec<String>>,
+}
+
+// Optional behaviour, set in the config file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub continuous_reading: bool,
+    pub preview: bool,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
@@ -15,9 +91,45 @@ pub enum Pane {
     Readme,
 }
 
+// Where each pane was drawn in the last render (whole block, with borders)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PaneAreas {
+    pub courses: Rect,
+    pub chapters: Rect,
+    pub lessons: Rect,
+    pub readme: Rect,
+    pub help: Rect,
+}
+
+// A course matching the search query, with the matched char indices
+pub struct FilteredCourse<'a> {
+    pub slug: &'a str,
+    pub title: &'a str,
+    pub title_matches: Vec<usize>,
+    pub slug_matches: Vec<usize>,
+}
+
+// A lesson in the readme, starting at the given line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadmePart {
+    pub chapter_no: usize,
+    pub lesson_no: usize,
+    pub line: usize,
+}
+
+// A lesson matching the lesson finder query, labeled "chapter.lesson Title"
+pub struct FilteredLesson {
+    pub chapter_no: usize,
+    pub lesson_no: usize,
+    pub label: String,
+    pub matches: Vec<usize>,
+}
+
 pub struct App {
     pub view: View,
     pub active_pane: Pane,
+    pub overlay: Overlay,
+    pub options: Options,
 
     // Data
     pub courses: Vec<(String, String)>, // (slug, title)
@@ -27,7 +139,15 @@ pub struct App {
 
     // Search
     pub search_query: String,
-    pub is_search_mode: bool,
+    pub search_cursor: usize, // Char index into the query being typed
+    pub chapter_filter: String,
+    pub lesson_filter: String,
+    pub readme_query: String,
+
+    // Past queries, browsed with Up/Down while typing
+    pub history: History,
+    history_back: usize, // Steps back from the end, 0 while editing the draft
+    history_draft: String,
 
     // Highlighted versions (ANSI strings)
     pub chapters_highlighted: String,
@@ -45,34 +165,96 @@ pub struct App {
     pub selected_chapter_no: Option<usize>,
     pub selected_lesson_no: Option<usize>,
 
-    // In-memory cache
+    // Cache (persisted between sessions)
     pub cache: Cache,
 
-    // Readme scroll position
+    // Readme scroll position (in wrapped rows)
     pub readme_scroll: usize,
 
+    // Wrapped layout from the last render: (source line, first char) of each row
+    pub readme_rows: Vec<(usize, usize)>,
+
+    // Pane layout from the last render, for page motions and mouse hit-testing
+    pub areas: PaneAreas,
+    pub last_click: Option<Click>,
+
+    // Vim-style count typed before a motion (e.g., the 5 in 5j)
+    pub pending_count: Option<usize>,
+
+    // Readme position (line, char) to scroll to once it has been laid out
+    pub readme_jump: Option<(usize, usize)>,
+
+    // Lessons making up the readme, which holds several in continuous reading
+    pub readme_parts: Vec<ReadmePart>,
+    pub reading_stalled_at: Option<(usize, usize)>, // (chapter, lesson) reading on failed after
+    pub is_chapter_view: bool,                      // Showing a whole chapter
+    pub is_zen_mode: bool,                          // Only the readme, centered
+    pub layout: PaneLayout,
+
+    // Lessons or readme of the selection, loaded once it settles
+    pub preview: Option<Preview>,
+
+    // Search matches in the current readme
+    pub readme_matches: Vec<ReadmeMatch>,
+    pub current_match: Option<usize>,
+
+    // Search across all lessons of the course
+    pub indexing: Option<Indexing>,
+    pub course_search_query: String,
+    pub course_hits: Vec<CourseHit>,
+    pub course_hit_state: ListState,
+
+    // Quick jump to any lesson in the course
+    pub lesson_finder_query: String,
+    pub lesson_finder_state: ListState,
+    pub course_lessons: Vec<(usize, usize, String)>, // (chapter no., lesson no., title)
+
+    // Table of contents for the current readme
+    pub toc: Vec<TocEntry>,
+    pub toc_state: ListState,
+
+    // Help overlay listing the keys for the pane it was opened from
+    pub help_scroll: usize,
+
     // Status/error message
     pub status: String,
+
+    // Colors and syntax highlighting theme
+    pub theme: Theme,
+    pub keymap: Keymap,
 }
 
 impl Default for App {
     fn default() -> Self {
-        Self::new()
+        Self::new(
+            Theme::default(),
+            Keymap::default(),
+            Cache::default(),
+            History::default(),
+        )
     }
 }
 
 impl App {
     #[must_use]
-    pub fn new() -> Self {
+    pub fn new(theme: Theme, keymap: Keymap, cache: Cache, history: History) -> Self {
         let mut app = Self {
             view: View::CourseList,
             active_pane: Pane::Chapters,
+            overlay: Overlay::None,
+            options: Options::default(),
             courses: Vec::new(),
             chapters: Vec::new(),
             lessons: Vec::new(),
             readme: String::new(),
             search_query: String::new(),
-            is_search_mode: false,
+            readme_query: String::new(),
+            search_cursor: 0,
+            chapter_filter: String::new(),
+            lesson_filter: String::new(),
+            history,
+            history_back: 0,
+            history_draft: String::new(),
             chapters_highlighted: String::new(),
             lessons_highlighted: String::new(),
             course_state: ListState::default(),
@@ -83,50 +265,103 @@ impl App {
             selected_course_uuid: None,
             selected_chapter_no: None,
             selected_lesson_no: None,
-            cache: Cache::default(),
+            cache,
             readme_scroll: 0,
+            readme_rows: Vec::new(),
+            areas: PaneAreas::default(),
+            last_click: None,
+            pending_count: None,
+            readme_jump: None,
+            readme_parts: Vec::new(),
+            reading_stalled_at: None,
+            is_chapter_view: false,
+            is_zen_mode: false,
+            layout: PaneLayout::default(),
+            preview: None,
+            indexing: None,
+            readme_matches: Vec::new(),
+            current_match: None,
+            course_search_query: String::new(),
+            course_hits: Vec::new(),
+            course_hit_state: ListState::default(),
+            lesson_finder_query: String::new(),
+            lesson_finder_state: ListState::default(),
+            course_lessons: Vec::new(),
+            toc: Vec::new(),
+            toc_state: ListState::default(),
+            help_scroll: 0,
             status: String::from("Loading courses..."),
+            theme,
+            keymap,
         };
         app.load_courses();
         app
     }
 
     pub fn load_courses(&mut self) {
-        if let Some(cached) = self.cache.courses() {
-            self.courses.clone_from(cached);
-            if !self.get_filtered_courses().is_empty() {
-                self.course_state.select(Some(0));
+        let mut refresh_error = None;
+        if self.cache.courses_stale() {
+            match fetch::get_course_slugs() {
+                Ok(courses) => self.cache.set_courses(courses),
+                Err(e) => refresh_error = Some(e),
             }
-            self.status = format!("Loaded {} courses", self.courses.len());
-            return;
         }
 
-        match fetch::get_course_slugs() {
-            Ok(courses) => {
-                self.courses.clone_from(&courses);
-                self.cache.set_courses(courses);
-                if !self.get_filtered_courses().is_empty() {
-                    self.course_state.select(Some(0));
-                }
-                self.status = format!("Loaded {} courses", self.courses.len());
-            }
-            Err(e) => {
+        let Some(courses) = self.cache.courses() else {
+            if let Some(e) = refresh_error {
                 self.status = format!("Error loading courses: {}", e);
             }
+            return;
+        };
+
+        self.courses.clone_from(courses);
+        if !self.get_filtered_courses().is_empty() {
+            self.course_state.select(Some(0));
         }
+        // An old list still beats none when offline
+        self.status = match refresh_error {
+            Some(e) => format!(
+                "Loaded {} cached courses (error updating: {})",
+                self.courses.len(),
+                e
+            ),
+            None => format!("Loaded {} courses", self.courses.len()),
+        };
     }
 
     #[must_use]
-    pub fn get_filtered_courses(&self) -> Vec<&(String, String)> {
-        if self.search_query.is_empty() {
-            self.courses.iter().collect()
-        } else {
-            let query = self.search_query.to_lowercase();
-            self.courses
-                .iter()
-                .filter(|(_, title)| title.to_lowercase().contains(&query))
-                .collect()
+    pub fn get_filtered_courses(&self) -> Vec<FilteredCourse<'_>> {
+        let mut filtered: Vec<(i64, u64, FilteredCourse)> = self
+            .courses
+            .iter()
+            .filter_map(|(slug, title)| {
+                let title_match = fuzzy_match(&self.search_query, title);
+                let slug_match = fuzzy_match(&self.search_query, slug);
+
+                // Rank by whichever of the title and slug matches better
+                let (score, title_matches, slug_matches) = match (title_match, slug_match) {
+                    (Some(t), Some(s)) if s.score > t.score => (s.score, Vec::new(), s.indices),
+                    (Some(t), _) => (t.score, t.indices, Vec::new()),
+                    (None, Some(s)) => (s.score, Vec::new(), s.indices),
+                    (None, None) => return None,
+                };
+
+                let course = FilteredCourse {
+                    slug,
+                    title,
+                    title_matches,
+                    slug_matches,
+                };
+                Some((score, self.cache.frecency(slug), course))
+            })
+            .collect();
+
+        // Keep the usual order until there's something to rank by
+        if !self.search_query.is_empty() {
+            filtered.sort_by_key(|&(score, frecency, _)| Reverse((score, frecency)));
         }
+
+        filtered.into_iter().map(|(_, _, course)| course).collect()
     }
 
     pub fn load_chapters(&mut self) {
@@ -135,43 +370,62 @@ impl App {
             if idx >= filtered.len() {
                 return;
             }
-            let (slug, title) = filtered[idx];
-            (slug.clone(), title.clone())
+            (
+                filtered[idx].slug.to_owned(),
+                filtered[idx].title.to_owned(),
+            )
         } else {
             return;
         };
 
+        self.cache.record_course_open(&slug);
         self.selected_course_slug = Some(slug.clone());
         self.selected_course_title = Some(title);
         self.selected_course_uuid = None;
 
-        if let Some(chapters) = self.cache.chapters(&slug) {
-            self.chapters_highlighted = Self::highlight_numbered_list(chapters);
-            self.chapters = chapters.clone();
-            self.chapter_state.select(Some(0));
-            self.reset_lesson_content(true);
-            self.status = format!("Loaded {} chapters", self.chapters.len());
-            return;
-        }
+        let refresh_error = if self.cache.course_stale(&slug) {
+            self.refresh_course(&slug).err()
+        } else {
+            None
+        };
 
-        match fetch::get_chapters(&slug) {
-            Ok(chapters) => {
-                self.chapters_highlighted = Self::highlight_numbered_list(&chapters);
-                self.cache.set_chapters(slug, chapters.clone());
-                self.chapters = chapters;
-                self.chapter_state.select(Some(0));
-                self.reset_lesson_content(true);
-                self.status = format!("Loaded {} chapters", self.chapters.len());
-            }
-            Err(e) => {
+        let Some(chapters) = self.cache.chapters(&slug) else {
+            if let Some(e) = refresh_error {
                 self.status = format!("Error loading chapters: {}", e);
             }
+            return;
+        };
+
+        self.chapters_highlighted = Self::highlight_numbered_list(chapters, &self.theme);
+        self.chapters = chapters.clone();
+        self.chapter_filter.clear();
+        self.chapter_state.select(Some(0));
+        self.reset_lesson_content(true);
+        self.status = match refresh_error {
+            Some(e) => format!(
+                "Loaded {} cached chapters (error updating: {})",
+                self.chapters.len(),
+                e
+            ),
+            None => format!("Loaded {} chapters", self.chapters.len()),
+        };
+    }
+
+    // Fetch a course's chapters, and its lessons again if they were cached, only
+    // replacing the cached lists once everything has been fetched
+    fn refresh_course(&mut self, slug: &str) -> Result<(), anyhow::Error> {
+        let chapters = fetch::get_chapters(slug)?;
+        if self.cache.chapters(slug).is_some() {
+            let course_uuid = self.ensure_course_uuid()?;
+            let lessons = fetch::get_all_lessons_by_course_id(&course_uuid)?;
+            self.cache.set_all_lessons(&course_uuid, lessons);
         }
+        self.cache.set_chapters(slug.to_owned(), chapters);
+        Ok(())
     }
 
     pub fn load_lessons(&mut self) {
-        if let (Some(_), Some(ch_idx)) = (&self.selected_course_slug, self.chapter_state.selected())
-        {
+        if let (Some(_), Some(ch_idx)) = (&self.selected_course_slug, self.selected_chapter_idx()) {
             let ch_no = ch_idx + 1;
             self.selected_chapter_no = Some(ch_no);
             self.selected_lesson_no = None;
@@ -185,22 +439,32 @@ impl App {
             };
 
             if let Some(lessons) = self.cache.lessons(&course_uuid, ch_no) {
-                self.lessons_highlighted = Self::highlight_numbered_list(lessons);
+                self.lessons_highlighted = Self::highlight_numbered_list(lessons, &self.theme);
                 self.lessons = lessons.clone();
+                self.lesson_filter.clear();
                 self.lesson_state.select(Some(0));
                 self.readme.clear();
+                self.readme_parts.clear();
+                self.is_chapter_view = false;
+                self.toc.clear();
+                self.clear_readme_matches();
                 self.status = format!("Loaded {} lessons", self.lessons.len());
                 return;
             }
 
             match fetch::get_lessons_by_course_id(&course_uuid, ch_no) {
                 Ok(lessons) => {
-                    self.lessons_highlighted = Self::highlight_numbered_list(&lessons);
+                    self.lessons_highlighted = Self::highlight_numbered_list(&lessons, &self.theme);
                     self.cache
                         .set_lessons(course_uuid.clone(), ch_no, lessons.clone());
                     self.lessons = lessons;
+                    self.lesson_filter.clear();
                     self.lesson_state.select(Some(0));
                     self.readme.clear();
+                    self.readme_parts.clear();
+                    self.is_chapter_view = false;
+                    self.toc.clear();
+                    self.clear_readme_matches();
                     self.status = format!("Loaded {} lessons", self.lessons.len());
                 }
                 Err(e) => {
@@ -212,7 +476,7 @@ impl App {
 
     pub fn load_readme(&mut self) {
         if let (Some(ch_no), Some(lesson_idx)) =
-            (self.selected_chapter_no, self.lesson_state.selected())
+            (self.selected_chapter_no, self.selected_lesson_idx())
         {
             let lesson_no = lesson_idx + 1;
             self.selected_lesson_no = Some(lesson_no);
@@ -226,39 +490,25 @@ impl App {
                 }
             };
 
-            let lesson_id =
-                if let Some(cached_id) = self.cache.lesson_id(&course_uuid, ch_no, lesson_no) {
-                    cached_id.clone()
-                } else {
-                    match fetch::get_lesson_id_by_course_id(&course_uuid, ch_no, lesson_no) {
-                        Ok(fetched_id) => {
-                            self.cache.set_lesson_id(
-                                course_uuid.clone(),
-                                ch_no,
-                                lesson_no,
-                                fetched_id.clone(),
-                            );
-                            fetched_id
-                        }
-                        Err(e) => {
-                            self.status = format!("Error getting lesson ID: {}", e);
-                            return;
-                        }
-                    }
-                };
-
-            if let Some(cached_readme) = self.cache.readme(&lesson_id) {
-                self.readme = cached_readme.clone();
-                self.status = String::from("Lesson loaded");
-                return;
-            }
+            let lesson_id = match self.lesson_id(&course_uuid, ch_no, lesson_no) {
+                Ok(id) => id,
+                Err(e) => {
+                    self.status = format!("Error getting lesson ID: {}", e);
+                    return;
+                }
+            };
 
-            match fetch::get_readme_by_id(&lesson_id) {
+            match self.readme_by_id(&lesson_id) {
                 Ok(readme) => {
-                    let highlighted = Self::highlight_markdown(&readme);
-                    self.cache
-                        .set_readme(lesson_id.clone(), highlighted.clone());
-                    self.readme = highlighted;
+                    self.readme = readme;
+                    self.readme_parts = vec![ReadmePart {
+                        chapter_no: ch_no,
+                        lesson_no,
+                        line: 0,
+                    }];
+                    self.is_chapter_view = false;
+                    self.toc = toc::build(&self.readme);
+                    self.clear_readme_matches();
                     self.status = String::from("Lesson loaded");
                 }
                 Err(e) => {
@@ -268,6 +518,362 @@ impl App {
         }
     }
 
+    fn lesson_id(
+        &mut self,
+        course_uuid: &str,
+        ch_no: usize,
+        lesson_no: usize,
+    ) -> Result<String, anyhow::Error> {
+        if let Some(cached_id) = self.cache.lesson_id(course_uuid, ch_no, lesson_no) {
+            return Ok(cached_id.clone());
+        }
+
+        let fetched_id = fetch::get_lesson_id_by_course_id(course_uuid, ch_no, lesson_no)?;
+        self.cache
+            .set_lesson_id(course_uuid.to_owned(), ch_no, lesson_no, fetched_id.clone());
+        Ok(fetched_id)
+    }
+
+    /// In continuous reading, append the chapter's next lesson to the readme,
+    /// or open the next chapter once this one is done. Lessons without a
+    /// readme are skipped.
+    pub fn continue_reading(&mut self) {
+        let Some(&last) = self.readme_parts.last() else {
+            return;
+        };
+        // Don't fetch again on every scroll once it has failed here
+        if self.reading_stalled_at == Some((last.chapter_no, last.lesson_no)) {
+            return;
+        }
+
+        let found = self.ensure_course_uuid().and_then(|course_uuid| {
+            if let Some(found) =
+                self.next_readme(&course_uuid, last.chapter_no, last.lesson_no + 1)?
+            {
+                return Ok(Some((last.chapter_no, found)));
+            }
+
+            // Titles (and so lesson counts) of the later chapters
+            self.ensure_all_lessons()?;
+            for ch_no in last.chapter_no + 1..=self.chapters.len() {
+                if let Some(found) = self.next_readme(&course_uuid, ch_no, 1)? {
+                    return Ok(Some((ch_no, found)));
+                }
+            }
+            Ok(None)
+        });
+
+        let (ch_no, (lesson_no, readme)) = match found {
+            Ok(Some(found)) => found,
+            Ok(None) => {
+                self.reading_stalled_at = Some((last.chapter_no, last.lesson_no));
+                self.status = String::from("This is the last lesson in the course");
+                return;
+            }
+            Err(e) => {
+                self.reading_stalled_at = Some((last.chapter_no, last.lesson_no));
+                self.status = format!("Error loading readme: {}", e);
+                return;
+            }
+        };
+
+        if ch_no != last.chapter_no {
+            // The readme is cached by now
+            self.open_lesson(ch_no, lesson_no);
+            return;
+        }
+
+        let title = self.lessons.get(lesson_no - 1).map_or("", String::as_str);
+        let heading = lesson_heading(ch_no, lesson_no, title);
+        let line = append_lesson(&mut self.readme, &heading, &readme);
+
+        self.readme_parts.push(ReadmePart {
+            chapter_no: ch_no,
+            lesson_no,
+            line,
+        });
+        self.toc = self.build_toc();
+        if !self.readme_matches.is_empty() {
+            self.readme_matches = find_matches(&plain_lines(&self.readme), &self.readme_query);
+        }
+        self.status = format!("Continued with lesson {}.{}", ch_no, lesson_no);
+    }
+
+    // The first lesson of the chapter from `lesson_no` on that has a readme,
+    // with its readme
+    fn next_readme(
+        &mut self,
+        course_uuid: &str,
+        ch_no: usize,
+        lesson_no: usize,
+    ) -> Result<Option<(usize, String)>, anyhow::Error> {
+        let len = self.cache.lessons(course_uuid, ch_no).map_or(0, Vec::len);
+
+        for lesson_no in lesson_no..=len {
+            let lesson_id = self.lesson_id(course_uuid, ch_no, lesson_no)?;
+            match self.readme_by_id(&lesson_id) {
+                Ok(readme) => return Ok(Some((lesson_no, readme))),
+                Err(e) if e.is::<NoReadme>() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Show every lesson of the selected chapter as one readme
+    pub fn read_chapter(&mut self) {
+        let ch_idx = if self.active_pane == Pane::Chapters {
+            self.selected_chapter_idx()
+        } else {
+            self.selected_chapter_no.map(|ch_no| ch_no - 1)
+        };
+        let Some(ch_idx) = ch_idx else {
+            return;
+        };
+        let ch_no = ch_idx + 1;
+
+        // One request gets the IDs of every lesson in the course
+        if let Err(e) = self.ensure_all_lessons() {
+            self.status = format!("Error loading lessons: {}", e);
+            return;
+        }
+
+        self.chapter_filter.clear();
+        self.chapter_state.select(Some(ch_idx));
+        self.load_lessons();
+        let Some(course_uuid) = self.selected_course_uuid.clone() else {
+            return;
+        };
+
+        let lesson_ids: Vec<String> = (1..=self.lessons.len())
+            .filter_map(|lesson_no| self.cache.lesson_id(&course_uuid, ch_no, lesson_no))
+            .cloned()
+            .collect();
+        if lesson_ids.len() != self.lessons.len() {
+            self.status = String::from("Error loading chapter: missing lesson IDs");
+            return;
+        }
+
+        let readmes = self.readmes_by_ids(&lesson_ids);
+        let mut readme = String::new();
+        let mut parts = Vec::new();
+
+        for (lesson_idx, result) in readmes.into_iter().enumerate() {
+            let lesson_no = lesson_idx + 1;
+            // Coding-only lessons have no readme, which isn't an error
+            let body = match result {
+                Ok(body) => body,
+                Err(e) if e.is::<NoReadme>() => String::from("(no readme)"),
+                Err(e) => format!("Error loading readme: {}", e),
+            };
+            let heading = lesson_heading(ch_no, lesson_no, &self.lessons[lesson_idx]);
+            let line = append_lesson(&mut readme, &heading, &body);
+            parts.push(ReadmePart {
+                chapter_no: ch_no,
+                lesson_no,
+                line,
+            });
+        }
+
+        self.readme = readme;
+        self.readme_parts = parts;
+        self.is_chapter_view = true;
+        self.selected_lesson_no = Some(1);
+        self.toc = self.build_toc();
+        self.clear_readme_matches();
+        self.active_pane = Pane::Readme;
+        self.readme_scroll = 0;
+        self.status = format!("Loaded {} lessons of chapter {}", self.lessons.len(), ch_no);
+    }
+
+    // Fetches and highlights the readmes that aren't cached yet in parallel
+    fn readmes_by_ids(&mut self, lesson_ids: &[String]) -> Vec<Result<String, anyhow::Error>> {
+        let missing: Vec<&String> = lesson_ids
+            .iter()
+            .filter(|id| self.cache.readme(id).is_none())
+            .collect();
+        let loaded = load_readmes(
+            &missing
+                .iter()
+                .map(|id| (id.as_str(), self.cache.raw_readme(id).map(String::as_str)))
+                .collect::<Vec<_>>(),
+            &self.theme,
+        );
+
+        // Kept so that lessons that failed aren't fetched again one by one
+        let mut failures = HashMap::new();
+        for (id, result) in missing.into_iter().zip(loaded) {
+            match result {
+                Ok((raw, highlighted)) => {
+                    self.cache.set_raw_readme(id.clone(), raw);
+                    self.cache.set_readme(id.clone(), highlighted);
+                }
+                Err(e) => {
+                    failures.insert(id, e);
+                }
+            }
+        }
+
+        lesson_ids
+            .iter()
+            .map(|id| {
+                failures
+                    .remove(id)
+                    .map_or_else(|| self.readme_by_id(id), Err)
+            })
+            .collect()
+    }
+
+    // Headings in a readme of several lessons go under an entry per lesson
+    fn build_toc(&self) -> Vec<TocEntry> {
+        let mut entries = toc::build(&self.readme);
+        if self.readme_parts.len() < 2 {
+            return entries;
+        }
+
+        for entry in &mut entries {
+            entry.level += 1;
+        }
+        for part in &self.readme_parts {
+            let title = self
+                .lessons
+                .get(part.lesson_no - 1)
+                .map_or("", String::as_str);
+            entries.push(TocEntry {
+                level: 1,
+                title: format!("{}.{} {}", part.chapter_no, part.lesson_no, title),
+                line: part.line,
+            });
+        }
+
+        // Stable, so a lesson comes before a heading on its first line
+        entries.sort_by_key(|entry| (entry.line, entry.level));
+        entries
+    }
+
+    // Follow the lesson being read in a continuous readme
+    pub fn sync_reading_position(&mut self) {
+        if self.readme_parts.len() < 2 {
+            return;
+        }
+
+        let line = self.line_at_row(self.readme_scroll);
+        let Some(part) = self.readme_parts.iter().rev().find(|p| p.line <= line) else {
+            return;
+        };
+
+        if self.selected_lesson_no != Some(part.lesson_no) {
+            self.selected_lesson_no = Some(part.lesson_no);
+            let lesson_idx = part.lesson_no - 1;
+            let position = self
+                .filtered_lessons()
+                .iter()
+                .position(|&i| i == lesson_idx);
+            if position.is_some() {
+                self.lesson_state.select(position);
+            }
+        }
+    }
+
+    fn readme_by_id(&mut self, lesson_id: &str) -> Result<String, anyhow::Error> {
+        if let Some(cached_readme) = self.cache.readme(lesson_id) {
+            return Ok(cached_readme.clone());
+        }
+
+        let readme = if let Some(raw) = self.cache.raw_readme(lesson_id) {
+            raw.clone()
+        } else {
+            let fetched = fetch::get_readme_by_id(lesson_id)?;
+            self.cache
+                .set_raw_readme(lesson_id.to_owned(), fetched.clone());
+            fetched
+        };
+
+        let highlighted = Self::highlight_markdown(&readme, &self.theme);
+        self.cache
+            .set_readme(lesson_id.to_owned(), highlighted.clone());
+        Ok(highlighted)
+    }
+
+    /// Select and load the given lesson, focusing the readme
+    pub fn open_lesson(&mut self, ch_no: usize, lesson_no: usize) {
+        if ch_no == 0 || ch_no > self.chapters.len() || lesson_no == 0 {
+            return;
+        }
+
+        self.chapter_filter.clear();
+        self.chapter_state.select(Some(ch_no - 1));
+        self.load_lessons();
+        if lesson_no > self.lessons.len() {
+            return;
+        }
+
+        self.lesson_state.select(Some(lesson_no - 1));
+        self.load_readme();
+        if !self.readme.is_empty() {
+            self.active_pane = Pane::Readme;
+            self.readme_scroll = 0;
+        }
+    }
+
+    /// Open the lesson after the current one, rolling over into the next chapter
+    pub fn next_lesson(&mut self) {
+        let (Some(ch_no), Some(lesson_no)) = (self.selected_chapter_no, self.selected_lesson_no)
+        else {
+            return;
+        };
+
+        if lesson_no < self.lessons.len() {
+            self.open_lesson(ch_no, lesson_no + 1);
+            return;
+        }
+
+        match self.chapter_with_lessons(ch_no + 1..=self.chapters.len()) {
+            Ok(Some((next_ch, _))) => self.open_lesson(next_ch, 1),
+            Ok(None) => self.status = String::from("This is the last lesson in the course"),
+            Err(e) => self.status = format!("Error loading lessons: {}", e),
+        }
+    }
+
+    /// Open the lesson before the current one, rolling back into the previous chapter
+    pub fn prev_lesson(&mut self) {
+        let (Some(ch_no), Some(lesson_no)) = (self.selected_chapter_no, self.selected_lesson_no)
+        else {
+            return;
+        };
+
+        if lesson_no > 1 {
+            self.open_lesson(ch_no, lesson_no - 1);
+            return;
+        }
+
+        match self.chapter_with_lessons((1..ch_no).rev()) {
+            Ok(Some((prev_ch, last))) => self.open_lesson(prev_ch, last),
+            Ok(None) => self.status = String::from("This is the first lesson in the course"),
+            Err(e) => self.status = format!("Error loading lessons: {}", e),
+        }
+    }
+
+    // The first of the chapters that has any lessons, with its lesson count
+    fn chapter_with_lessons(
+        &mut self,
+        chapters: impl Iterator<Item = usize>,
+    ) -> Result<Option<(usize, usize)>, anyhow::Error> {
+        let course_uuid = self.ensure_course_uuid()?;
+        for ch_no in chapters {
+            if self.cache.lessons(&course_uuid, ch_no).is_none() {
+                self.ensure_all_lessons()?;
+            }
+            let count = self.cache.lessons(&course_uuid, ch_no).map_or(0, Vec::len);
+            if count > 0 {
+                return Ok(Some((ch_no, count)));
+            }
+        }
+
+        Ok(None)
+    }
+
     fn ensure_course_uuid(&mut self) -> Result<String, anyhow::Error> {
         if let Some(uuid) = self.selected_course_uuid.clone() {
             return Ok(uuid);
@@ -289,25 +895,34 @@ impl App {
         Ok(uuid)
     }
 
-    fn highlight_numbered_list(items: &[String]) -> String {
+    fn highlight_numbered_list(items: &[String], theme: &Theme) -> String {
         let md: String = items
             .iter()
             .enumerate()
             .map(|(i, title)| format!("{}. {}", i + 1, title))
             .collect::<Vec<_>>()
             .join("\n");
-        highlight(&md, "markdown").unwrap_or(md)
+        highlight(&md, "markdown", theme).unwrap_or(md)
     }
 
-    fn highlight_markdown(content: &str) -> String {
-        let prettified = prettify(content).unwrap_or_else(|_| content.to_owned());
-        highlight(&prettified, "markdown").unwrap_or(prettified)
+    #[must_use]
+    pub fn highlight_markdown(content: &str, theme: &Theme) -> String {
+        let content = render_math(content);
+        let prettified = prettify(&content).unwrap_or(content);
+        let prettified = mark_display_math(&prettified);
+        highlight(&prettified, "markdown", theme).unwrap_or(prettified)
     }
 
     pub fn reset_lesson_content(&mut self, reset_state: bool) {
         self.lessons.clear();
         self.lessons_highlighted.clear();
+        self.lesson_filter.clear();
+        self.readme_query.clear();
         self.readme.clear();
+        self.readme_parts.clear();
+        self.is_chapter_view = false;
+        self.toc.clear();
+        self.clear_readme_matches();
         self.selected_chapter_no = None;
         self.selected_lesson_no = None;
         if reset_state {
@@ -315,36 +930,881 @@ impl App {
         }
     }
 
-    pub const fn enter_search(&mut self) {
-        self.is_search_mode = true;
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.pending_count.unwrap_or(0);
+        self.pending_count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+    }
+
+    /// The pending count (1 if none was typed), clearing it
+    pub const fn take_count(&mut self) -> usize {
+        match self.pending_count.take() {
+            Some(count) if count > 0 => count,
+            _ => 1,
+        }
+    }
+
+    /// Rows of the readme that fit in its pane, inside the borders and header
+    #[must_use]
+    pub fn readme_height(&self) -> usize {
+        usize::from(self.areas.readme.height.saturating_sub(3))
+    }
+
+    /// Scrolling stops once the last wrapped row is at the bottom of the pane
+    #[must_use]
+    pub fn readme_max_scroll(&self) -> usize {
+        self.readme_rows.len().saturating_sub(self.readme_height())
+    }
+
+    /// Keymap modes to look keys up in, most specific first
+    #[must_use]
+    pub const fn key_modes(&self) -> &'static [Mode] {
+        match self.overlay {
+            Overlay::None => self.pane_modes(),
+            Overlay::Search => &[Mode::Search],
+            Overlay::CourseSearch | Overlay::LessonFinder => &[Mode::Picker],
+            Overlay::Toc => &[Mode::Toc],
+            Overlay::Help => &[Mode::Help],
+        }
+    }
+
+    /// Keymap modes for the focused pane, which the help overlay lists
+    #[must_use]
+    pub const fn pane_modes(&self) -> &'static [Mode] {
+        match (self.view, self.active_pane) {
+            (View::CourseList, _) => &[Mode::CourseList, Mode::Normal],
+            (View::CourseContent, Pane::Chapters) => &[Mode::Chapters, Mode::Normal],
+            (View::CourseContent, Pane::Lessons) => &[Mode::Lessons, Mode::Normal],
+            (View::CourseContent, Pane::Readme) => &[Mode::Readme, Mode::Normal],
+        }
+    }
+
+    #[must_use]
+    pub fn in_readme(&self) -> bool {
+        self.view == View::CourseContent && self.active_pane == Pane::Readme
+    }
+
+    pub fn enter_search(&mut self) {
+        match self.view {
+            View::CourseList => {}
+            View::CourseContent if self.active_pane == Pane::Readme => {
+                if self.readme.is_empty() {
+                    return;
+                }
+                self.readme_query.clear();
+                self.clear_readme_matches();
+            }
+            View::CourseContent => {
+                if self.active_pane == Pane::Lessons && self.lessons.is_empty() {
+                    return;
+                }
+            }
+        }
+
+        self.overlay = Overlay::Search;
+        self.search_cursor = self.query().chars().count();
+        self.history_back = 0;
     }
 
     pub fn exit_search(&mut self) {
-        self.is_search_mode = false;
-        self.search_query.clear();
-        self.course_state.select(Some(0));
+        self.overlay = Overlay::None;
+        match (self.view, self.active_pane) {
+            (View::CourseList, _) => {
+                self.search_query.clear();
+                self.course_state.select(Some(0));
+            }
+            (View::CourseContent, Pane::Readme) => {
+                self.readme_query.clear();
+                self.clear_readme_matches();
+            }
+            // Show the whole list again, keeping the selected item selected
+            (View::CourseContent, Pane::Chapters) => {
+                let ch_idx = self.selected_chapter_idx().unwrap_or(0);
+                self.chapter_filter.clear();
+                self.chapter_state.select(Some(ch_idx));
+            }
+            (View::CourseContent, Pane::Lessons) => {
+                let lesson_idx = self.selected_lesson_idx().unwrap_or(0);
+                self.lesson_filter.clear();
+                self.lesson_state.select(Some(lesson_idx));
+            }
+        }
+    }
+
+    pub fn submit_search(&mut self) {
+        self.overlay = Overlay::None;
+        let query = self.query().to_owned();
+        self.history.push(&query);
+        if self.in_readme() && !self.readme_query.is_empty() {
+            self.status = if self.readme_matches.is_empty() {
+                format!("Not found: {}", self.readme_query)
+            } else {
+                format!("Found {} matches", self.readme_matches.len())
+            };
+        }
+    }
+
+    pub fn edit_search(&mut self, edit: Edit) {
+        let mut cursor = self.search_cursor;
+        input::apply(self.query_mut(), &mut cursor, edit);
+        self.search_cursor = cursor;
+
+        if !matches!(edit, Edit::Left | Edit::Right | Edit::Home | Edit::End) {
+            self.history_back = 0;
+            self.update_search();
+        }
+    }
+
+    pub fn history_prev(&mut self) {
+        if self.history_back >= self.history.len() {
+            return;
+        }
+        if self.history_back == 0 {
+            self.history_draft = self.query().to_owned();
+        }
+        self.history_back += 1;
+        self.recall_history();
+    }
+
+    pub fn history_next(&mut self) {
+        if self.history_back == 0 {
+            return;
+        }
+        self.history_back -= 1;
+        self.recall_history();
+    }
+
+    // Replace the query with the current history entry, or the draft
+    fn recall_history(&mut self) {
+        let query = self
+            .history
+            .get(self.history_back)
+            .cloned()
+            .unwrap_or_else(|| self.history_draft.clone());
+        self.search_cursor = query.chars().count();
+        *self.query_mut() = query;
+        self.update_search();
+    }
+
+    /// The text typed in search mode, which filters the active pane
+    #[must_use]
+    pub fn query(&self) -> &str {
+        match (self.view, self.active_pane) {
+            (View::CourseContent, Pane::Chapters) => &self.chapter_filter,
+            (View::CourseContent, Pane::Lessons) => &self.lesson_filter,
+            (View::CourseContent, Pane::Readme) => &self.readme_query,
+            (View::CourseList, _) => &self.search_query,
+        }
     }
 
-    pub const fn submit_search(&mut self) {
-        self.is_search_mode = false;
+    const fn query_mut(&mut self) -> &mut String {
+        match (self.view, self.active_pane) {
+            (View::CourseContent, Pane::Chapters) => &mut self.chapter_filter,
+            (View::CourseContent, Pane::Lessons) => &mut self.lesson_filter,
+            (View::CourseContent, Pane::Readme) => &mut self.readme_query,
+            (View::CourseList, _) => &mut self.search_query,
+        }
     }
 
-    pub fn append_search(&mut self, c: char) {
-        self.search_query.push(c);
+    fn update_search(&mut self) {
         // Reset selection to top when filtering changes to avoid out-of-bounds
-        if self.get_filtered_courses().is_empty() {
-            self.course_state.select(None);
+        let len = match (self.view, self.active_pane) {
+            (View::CourseList, _) => self.get_filtered_courses().len(),
+            (View::CourseContent, Pane::Chapters) => self.filtered_chapters().len(),
+            (View::CourseContent, Pane::Lessons) => self.filtered_lessons().len(),
+            (View::CourseContent, Pane::Readme) => {
+                self.update_readme_matches();
+                return;
+            }
+        };
+        let selected = if len == 0 { None } else { Some(0) };
+
+        match (self.view, self.active_pane) {
+            (View::CourseContent, Pane::Chapters) => self.chapter_state.select(selected),
+            (View::CourseContent, Pane::Lessons) => self.lesson_state.select(selected),
+            _ => self.course_state.select(selected),
+        }
+    }
+
+    /// Indices of the chapters matching the chapter filter
+    #[must_use]
+    pub fn filtered_chapters(&self) -> Vec<usize> {
+        filter_titles(&self.chapters, &self.chapter_filter)
+    }
+
+    /// Indices of the lessons matching the lesson filter
+    #[must_use]
+    pub fn filtered_lessons(&self) -> Vec<usize> {
+        filter_titles(&self.lessons, &self.lesson_filter)
+    }
+
+    // Index into `chapters` of the selected item, which may be filtered
+    #[must_use]
+    pub fn selected_chapter_idx(&self) -> Option<usize> {
+        let idx = self.chapter_state.selected()?;
+        self.filtered_chapters().get(idx).copied()
+    }
+
+    #[must_use]
+    pub fn selected_lesson_idx(&self) -> Option<usize> {
+        let idx = self.lesson_state.selected()?;
+        self.filtered_lessons().get(idx).copied()
+    }
+
+    fn update_readme_matches(&mut self) {
+        self.readme_matches = find_matches(&plain_lines(&self.readme), &self.readme_query);
+        self.current_match = None;
+        if !self.readme_matches.is_empty() {
+            self.jump_to_match(0);
+        }
+    }
+
+    fn clear_readme_matches(&mut self) {
+        self.readme_matches.clear();
+        self.current_match = None;
+    }
+
+    fn jump_to_match(&mut self, idx: usize) {
+        if let Some(m) = self.readme_matches.get(idx) {
+            self.readme_jump = Some((m.line, m.start));
+            self.current_match = Some(idx);
+        }
+    }
+
+    pub fn next_match(&mut self) {
+        if self.readme_matches.is_empty() {
+            return;
+        }
+        let next = self
+            .current_match
+            .map_or(0, |idx| (idx + 1) % self.readme_matches.len());
+        self.jump_to_match(next);
+    }
+
+    pub fn prev_match(&mut self) {
+        if self.readme_matches.is_empty() {
+            return;
+        }
+        let len = self.readme_matches.len();
+        let prev = self
+            .current_match
+            .map_or(len - 1, |idx| (idx + len - 1) % len);
+        self.jump_to_match(prev);
+    }
+
+    /// Wrapped row where the given char of a readme line appears
+    #[must_use]
+    pub fn row_of(&self, line: usize, ch: usize) -> usize {
+        if self.readme_rows.is_empty() {
+            return line;
+        }
+        self.readme_rows
+            .partition_point(|&pos| pos <= (line, ch))
+            .saturating_sub(1)
+    }
+
+    /// Readme line shown on the given wrapped row
+    #[must_use]
+    pub fn line_at_row(&self, row: usize) -> usize {
+        self.readme_rows.get(row).map_or(row, |&(line, _)| line)
+    }
+
+    /// Search the course, indexing it in the background first if needed
+    pub fn open_course_search(&mut self) {
+        let course_uuid = match self.ensure_course_uuid() {
+            Ok(uuid) => uuid,
+            Err(e) => {
+                self.status = format!("Error indexing course: {}", e);
+                return;
+            }
+        };
+
+        if self.cache.course_index(&course_uuid).is_some() {
+            self.overlay = Overlay::CourseSearch;
+            self.update_course_hits();
+        } else if self
+            .indexing
+            .as_ref()
+            .is_some_and(|i| i.course_uuid == course_uuid)
+        {
+            self.status = String::from("Still indexing the course...");
+        } else if let Err(e) = self.start_indexing(course_uuid) {
+            self.status = format!("Error indexing course: {}", e);
+        }
+    }
+
+    pub const fn close_course_search(&mut self) {
+        self.overlay = Overlay::None;
+    }
+
+    pub fn append_course_search(&mut self, c: char) {
+        self.course_search_query.push(c);
+        self.update_course_hits();
+    }
+
+    pub fn pop_course_search(&mut self) {
+        self.course_search_query.pop();
+        self.update_course_hits();
+    }
+
+    fn update_course_hits(&mut self) {
+        self.course_hits = self
+            .selected_course_uuid
+            .as_deref()
+            .and_then(|uuid| self.cache.course_index(uuid))
+            .map(|index| index.search(&self.course_search_query))
+            .unwrap_or_default();
+
+        if self.course_hits.is_empty() {
+            self.course_hit_state.select(None);
         } else {
-            self.course_state.select(Some(0));
+            self.course_hit_state.select(Some(0));
+        }
+    }
+
+    pub const fn course_hit_up(&mut self) {
+        if let Some(idx) = self.course_hit_state.selected()
+            && idx > 0
+        {
+            self.course_hit_state.select(Some(idx - 1));
+        }
+    }
+
+    pub const fn course_hit_down(&mut self) {
+        if let Some(idx) = self.course_hit_state.selected()
+            && idx + 1 < self.course_hits.len()
+        {
+            self.course_hit_state.select(Some(idx + 1));
+        }
+    }
+
+    pub fn open_course_hit(&mut self) {
+        let Some(hit) = self
+            .course_hit_state
+            .selected()
+            .and_then(|idx| self.course_hits.get(idx))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.overlay = Overlay::None;
+        self.open_lesson(hit.chapter_no, hit.lesson_no);
+        if self.readme.is_empty() {
+            return;
+        }
+
+        // Carry the query over, as if it had been searched within the lesson
+        self.readme_query.clone_from(&self.course_search_query);
+        self.readme_matches = find_matches(&plain_lines(&self.readme), &self.readme_query);
+        self.current_match = self
+            .readme_matches
+            .iter()
+            .position(|m| m.line == hit.line && m.start == hit.start);
+        self.readme_jump = Some((hit.line, hit.start));
+    }
+
+    // Fetches and renders the readmes not cached yet on a background thread,
+    // which can take a while the first time
+    fn start_indexing(&mut self, course_uuid: String) -> Result<(), anyhow::Error> {
+        self.ensure_all_lessons()?;
+        let mut entries = Vec::new();
+
+        for ch_no in 1..=self.chapters.len() {
+            let titles = self
+                .cache
+                .lessons(&course_uuid, ch_no)
+                .cloned()
+                .unwrap_or_default();
+
+            for (lesson_idx, title) in titles.into_iter().enumerate() {
+                let lesson_no = lesson_idx + 1;
+                if let Some(lesson_id) = self.cache.lesson_id(&course_uuid, ch_no, lesson_no) {
+                    let lines = self.cache.plain_readme(lesson_id).cloned().or_else(|| {
+                        self.cache
+                            .readme(lesson_id)
+                            .map(|readme| plain_lines(readme))
+                    });
+                    entries.push(IndexEntry {
+                        chapter_no: ch_no,
+                        lesson_no,
+                        title,
+                        lesson_id: lesson_id.clone(),
+                        raw: self.cache.raw_readme(lesson_id).cloned(),
+                        lines,
+                    });
+                }
+            }
+        }
+
+        let theme = self.theme.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The indexing may have been cancelled in the meantime
+            let _ = sender.send(index_course(entries, &theme));
+        });
+
+        self.indexing = Some(Indexing {
+            course_uuid,
+            loading: receiver,
+        });
+        self.status = String::from("Indexing the course...");
+        Ok(())
+    }
+
+    /// How long the event loop can wait for input before checking on the indexing
+    #[must_use]
+    pub fn indexing_timeout(&self) -> Option<Duration> {
+        self.indexing.as_ref().map(|_| INDEXING_POLL_INTERVAL)
+    }
+
+    /// Cache the index once it's built, searching it if still in that course
+    pub fn poll_indexing(&mut self) {
+        let Some(indexing) = &self.indexing else {
+            return;
+        };
+
+        let result = match indexing.loading.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(anyhow!("Indexing panicked")),
+        };
+        let Some(indexing) = self.indexing.take() else {
+            return;
+        };
+
+        let indexed = match result {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                self.status = format!("Error indexing course: {}", e);
+                return;
+            }
+        };
+
+        for (lesson_id, raw, highlighted) in indexed.readmes {
+            self.cache.set_raw_readme(lesson_id.clone(), raw);
+            self.cache.set_readme(lesson_id, highlighted);
+        }
+        for (lesson_id, lines) in indexed.plain_readmes {
+            self.cache.set_plain_readme(lesson_id, lines);
+        }
+        self.cache
+            .set_course_index(indexing.course_uuid.clone(), indexed.index);
+        self.status = format!("Indexed {} lessons", indexed.lesson_count);
+
+        if self.selected_course_uuid.as_ref() == Some(&indexing.course_uuid)
+            && self.overlay == Overlay::None
+        {
+            self.overlay = Overlay::CourseSearch;
+            self.update_course_hits();
+        }
+    }
+
+    // Caches the titles and IDs of every lesson in the course in one request
+    fn ensure_all_lessons(&mut self) -> Result<(), anyhow::Error> {
+        let course_uuid = self.ensure_course_uuid()?;
+
+        let is_complete = (1..=self.chapters.len()).all(|ch_no| {
+            self.cache
+                .lessons(&course_uuid, ch_no)
+                .is_some_and(|lessons| {
+                    (1..=lessons.len())
+                        .all(|l| self.cache.lesson_id(&course_uuid, ch_no, l).is_some())
+                })
+        });
+        if is_complete {
+            return Ok(());
         }
+
+        let chapters = fetch::get_all_lessons_by_course_id(&course_uuid)?;
+        self.cache.set_all_lessons(&course_uuid, chapters);
+        Ok(())
     }
 
-    pub fn pop_search(&mut self) {
-        self.search_query.pop();
-        if self.get_filtered_courses().is_empty() {
-            self.course_state.select(None);
+    pub fn open_lesson_finder(&mut self) {
+        if let Err(e) = self.ensure_all_lessons() {
+            self.status = format!("Error loading lessons: {}", e);
+            return;
+        }
+
+        let Some(course_uuid) = self.selected_course_uuid.clone() else {
+            return;
+        };
+
+        self.course_lessons.clear();
+        for (ch_idx, _) in self.chapters.iter().enumerate() {
+            let ch_no = ch_idx + 1;
+            if let Some(titles) = self.cache.lessons(&course_uuid, ch_no) {
+                self.course_lessons.extend(
+                    titles
+                        .iter()
+                        .enumerate()
+                        .map(|(i, title)| (ch_no, i + 1, title.clone())),
+                );
+            }
+        }
+
+        self.overlay = Overlay::LessonFinder;
+        self.lesson_finder_query.clear();
+        self.update_lesson_finder();
+    }
+
+    pub const fn close_lesson_finder(&mut self) {
+        self.overlay = Overlay::None;
+    }
+
+    pub fn append_lesson_finder(&mut self, c: char) {
+        self.lesson_finder_query.push(c);
+        self.update_lesson_finder();
+    }
+
+    pub fn pop_lesson_finder(&mut self) {
+        self.lesson_finder_query.pop();
+        self.update_lesson_finder();
+    }
+
+    fn update_lesson_finder(&mut self) {
+        if self.get_filtered_lessons().is_empty() {
+            self.lesson_finder_state.select(None);
         } else {
-            self.course_state.select(Some(0));
+            self.lesson_finder_state.select(Some(0));
+        }
+    }
+
+    pub const fn lesson_finder_up(&mut self) {
+        if let Some(idx) = self.lesson_finder_state.selected()
+            && idx > 0
+        {
+            self.lesson_finder_state.select(Some(idx - 1));
+        }
+    }
+
+    pub fn lesson_finder_down(&mut self) {
+        let len = self.get_filtered_lessons().len();
+        if let Some(idx) = self.lesson_finder_state.selected()
+            && idx + 1 < len
+        {
+            self.lesson_finder_state.select(Some(idx + 1));
+        }
+    }
+
+    #[must_use]
+    pub fn get_filtered_lessons(&self) -> Vec<FilteredLesson> {
+        let mut filtered: Vec<(i64, FilteredLesson)> = self
+            .course_lessons
+            .iter()
+            .filter_map(|(ch_no, lesson_no, title)| {
+                let label = format!("{}.{} {}", ch_no, lesson_no, title);
+                let m = fuzzy_match(&self.lesson_finder_query, &label)?;
+                let lesson = FilteredLesson {
+                    chapter_no: *ch_no,
+                    lesson_no: *lesson_no,
+                    label,
+                    matches: m.indices,
+                };
+                Some((m.score, lesson))
+            })
+            .collect();
+
+        // Stable, so ties stay in course order
+        filtered.sort_by_key(|&(score, _)| Reverse(score));
+        filtered.into_iter().map(|(_, lesson)| lesson).collect()
+    }
+
+    pub fn open_lesson_finder_selection(&mut self) {
+        let Some(lesson) = self
+            .lesson_finder_state
+            .selected()
+            .and_then(|idx| self.get_filtered_lessons().into_iter().nth(idx))
+        else {
+            return;
+        };
+
+        self.overlay = Overlay::None;
+        self.open_lesson(lesson.chapter_no, lesson.lesson_no);
+    }
+
+    // Panes with something in them, in screen order
+    fn focusable_panes(&self) -> Vec<Pane> {
+        [
+            (Pane::Chapters, !self.chapters.is_empty()),
+            (Pane::Lessons, !self.lessons.is_empty()),
+            (Pane::Readme, !self.readme.is_empty()),
+        ]
+        .into_iter()
+        .filter_map(|(pane, has_content)| has_content.then_some(pane))
+        .collect()
+    }
+
+    /// Move the focus to a pane as it is, without loading anything
+    pub fn focus_pane(&mut self, pane: Pane) {
+        if self.focusable_panes().contains(&pane) {
+            self.active_pane = pane;
+        }
+    }
+
+    /// Focus the next (or previous) pane with content, wrapping around
+    pub fn cycle_pane(&mut self, forward: bool) {
+        let panes = self.focusable_panes();
+        let Some(idx) = panes.iter().position(|&pane| pane == self.active_pane) else {
+            return;
+        };
+        let next = if forward {
+            (idx + 1) % panes.len()
+        } else {
+            (idx + panes.len() - 1) % panes.len()
+        };
+        self.active_pane = panes[next];
+    }
+
+    pub const fn toggle_zen(&mut self) {
+        if self.readme.is_empty() {
+            return;
+        }
+        self.is_zen_mode = !self.is_zen_mode;
+        self.active_pane = Pane::Readme;
+    }
+
+    // The side pane the layout keys act on; from the readme, its neighbour
+    const fn resized_pane(&mut self) -> (&mut PaneSize, Rect) {
+        match self.active_pane {
+            Pane::Chapters => (&mut self.layout.chapters, self.areas.chapters),
+            Pane::Lessons | Pane::Readme => (&mut self.layout.lessons, self.areas.lessons),
+        }
+    }
+
+    pub fn widen_pane(&mut self, n: usize) {
+        self.resize_pane(
+            i32::try_from(n)
+                .unwrap_or(i32::MAX)
+                .saturating_mul(PANE_STEP),
+        );
+    }
+
+    pub fn narrow_pane(&mut self, n: usize) {
+        self.resize_pane(
+            i32::try_from(n)
+                .unwrap_or(i32::MAX)
+                .saturating_mul(-PANE_STEP),
+        );
+    }
+
+    // Panes hidden in zen mode or a narrow terminal keep their layout
+    fn resize_pane(&mut self, step: i32) {
+        let (size, area) = self.resized_pane();
+        if area.width == 0 {
+            return;
+        }
+        if size.collapsed {
+            size.collapsed = false;
+            return;
+        }
+
+        // Start from the width on screen, which may be fitted or clamped
+        let width = i32::from(area.width).saturating_add(step);
+        size.width = u16::try_from(width.clamp(MIN_PANE_WIDTH.into(), u16::MAX.into())).ok();
+    }
+
+    pub const fn toggle_collapse(&mut self) {
+        let (size, area) = self.resized_pane();
+        if area.width > 0 {
+            size.collapsed = !size.collapsed;
         }
     }
+
+    pub const fn open_help(&mut self) {
+        self.overlay = Overlay::Help;
+        self.help_scroll = 0;
+    }
+
+    pub const fn close_help(&mut self) {
+        self.overlay = Overlay::None;
+    }
+
+    pub const fn help_down(&mut self, n: usize) {
+        self.help_scroll = self.help_scroll.saturating_add(n);
+    }
+
+    pub const fn help_up(&mut self, n: usize) {
+        self.help_scroll = self.help_scroll.saturating_sub(n);
+    }
+
+    #[must_use]
+    pub fn help_page_size(&self) -> usize {
+        usize::from(self.areas.help.height.saturating_sub(2)).max(1)
+    }
+
+    pub fn open_toc(&mut self) {
+        if self.toc.is_empty() {
+            self.status = String::from("No headings in this lesson");
+            return;
+        }
+
+        self.overlay = Overlay::Toc;
+        let line = self.line_at_row(self.readme_scroll);
+        let current = toc::section_at(&self.toc, line).unwrap_or(0);
+        self.toc_state.select(Some(current));
+    }
+
+    pub const fn close_toc(&mut self) {
+        self.overlay = Overlay::None;
+    }
+
+    pub const fn toc_up(&mut self) {
+        if let Some(idx) = self.toc_state.selected()
+            && idx > 0
+        {
+            self.toc_state.select(Some(idx - 1));
+        }
+    }
+
+    pub const fn toc_down(&mut self) {
+        if let Some(idx) = self.toc_state.selected()
+            && idx + 1 < self.toc.len()
+        {
+            self.toc_state.select(Some(idx + 1));
+        }
+    }
+
+    pub fn toc_jump(&mut self) {
+        if let Some(entry) = self.toc_state.selected().and_then(|idx| self.toc.get(idx)) {
+            self.readme_jump = Some((entry.line, 0));
+        }
+        self.overlay = Overlay::None;
+    }
+
+    /// Course, chapter, and lesson titles for the current position
+    #[must_use]
+    pub fn breadcrumb(&self) -> Vec<String> {
+        let mut crumbs = Vec::new();
+
+        if let Some(title) = &self.selected_course_title {
+            crumbs.push(title.clone());
+        }
+        if let Some(ch_no) = self.selected_chapter_no
+            && let Some(title) = self.chapters.get(ch_no - 1)
+        {
+            crumbs.push(format!("{}. {}", ch_no, title));
+        }
+        if let Some(lesson_no) = self.selected_lesson_no
+            && let Some(title) = self.lessons.get(lesson_no - 1)
+        {
+            crumbs.push(format!("{}. {}", lesson_no, title));
+        }
+
+        crumbs
+    }
+
+    #[must_use]
+    pub fn current_section(&self) -> Option<&TocEntry> {
+        let line = self.line_at_row(self.readme_scroll);
+        toc::section_at(&self.toc, line).map(|idx| &self.toc[idx])
+    }
+}
+
+// Fetches (unless the raw readme is given) and highlights readmes, in batches
+// of parallel requests, returning each raw readme with its highlighted one
+fn load_readmes(
+    readmes: &[(&str, Option<&str>)],
+    theme: &Theme,
+) -> Vec<Result<(String, String), anyhow::Error>> {
+    let mut loaded = Vec::with_capacity(readmes.len());
+
+    for batch in readmes.chunks(MAX_PARALLEL_FETCHES) {
+        thread::scope(|scope| {
+            // Spawn every fetch before waiting on any of them
+            let mut handles = Vec::new();
+            for &(id, raw) in batch {
+                handles.push(scope.spawn(move || {
+                    let raw = match raw {
+                        Some(raw) => raw.to_owned(),
+                        None => fetch::get_readme_by_id(id)?,
+                    };
+                    let highlighted = App::highlight_markdown(&raw, theme);
+                    Ok((raw, highlighted))
+                }));
+            }
+
+            loaded.extend(handles.into_iter().map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("Readme loading panicked")))
+            }));
+        });
+    }
+
+    loaded
+}
+
+// Runs on a background thread, loading the readmes the entries are missing
+fn index_course(entries: Vec<IndexEntry>, theme: &Theme) -> Result<Indexed, anyhow::Error> {
+    let missing: Vec<(&str, Option<&str>)> = entries
+        .iter()
+        .filter(|entry| entry.lines.is_none())
+        .map(|entry| (entry.lesson_id.as_str(), entry.raw.as_deref()))
+        .collect();
+    let mut loaded = load_readmes(&missing, theme).into_iter();
+
+    let mut lessons = Vec::new();
+    let mut readmes = Vec::new();
+    let mut plain_readmes = Vec::new();
+
+    for entry in entries {
+        let lines = match entry.lines {
+            Some(lines) => lines,
+            None => match loaded.next() {
+                Some(Ok((raw, highlighted))) => {
+                    let lines = plain_lines(&highlighted);
+                    readmes.push((entry.lesson_id.clone(), raw, highlighted));
+                    lines
+                }
+                // Not every lesson has a readme, but a partial index can't be cached
+                Some(Err(e)) if e.is::<NoReadme>() => continue,
+                Some(Err(e)) => return Err(e),
+                None => return Err(anyhow!("Missing readme of {}", entry.lesson_id)),
+            },
+        };
+
+        plain_readmes.push((entry.lesson_id, lines.clone()));
+        lessons.push(IndexedLesson {
+            chapter_no: entry.chapter_no,
+            lesson_no: entry.lesson_no,
+            title: entry.title,
+            lines,
+        });
+    }
+
+    Ok(Indexed {
+        lesson_count: lessons.len(),
+        index: CourseIndex::build(lessons),
+        readmes,
+        plain_readmes,
+    })
+}
+
+// Keep the list order, since titles are numbered
+fn filter_titles(titles: &[String], query: &str) -> Vec<usize> {
+    titles
+        .iter()
+        .enumerate()
+        .filter(|(_, title)| fuzzy_match(query, title).is_some())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn lesson_heading(ch_no: usize, lesson_no: usize, title: &str) -> String {
+    format!("\x1b[1m── {}.{} {} ──\x1b[0m", ch_no, lesson_no, title)
+}
+
+// Appends a lesson below its heading, returning the heading's line
+fn append_lesson(readme: &mut String, heading: &str, body: &str) -> usize {
+    readme.truncate(readme.trim_end_matches('\n').len());
+    if !readme.is_empty() {
+        readme.push_str("\n\n");
+    }
+    let line = readme.lines().count();
+
+    readme.push_str(heading);
+    readme.push_str("\n\n");
+    readme.push_str(body);
+    line
 }
@@ -1,45 +1,148 @@
-use std::collections::HashMap;
+use crate::search::CourseIndex;
+use anyhow::Context;
+use etcetera::{BaseStrategy, choose_base_strategy};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, mem,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// Course, chapter, and lesson lists older than this are fetched again
+const MAX_AGE_SECS: u64 = 7 * 86_400;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct ChapterKey {
     course_uuid: String,
     chapter_no: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct LessonKey {
     course_uuid: String,
     chapter_no: usize,
     lesson_no: usize,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct CourseOpens {
+    count: u64,
+    last_opened: u64, // Unix seconds
+}
+
+// A lesson from the persisted cache, for searching outside the TUI
+pub struct CachedLesson<'a> {
+    pub course_slug: &'a str,
+    pub chapter_no: usize,
+    pub lesson_no: usize,
+    pub title: &'a str,
+    pub readme: Option<&'a str>,
+}
+
+// Everything but the highlighted readmes and search indexes is persisted
+// between sessions, with raw readmes so that changing themes still works
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Cache {
     courses: Option<Vec<(String, String)>>,
     chapters: HashMap<String, Vec<String>>,
     course_uuids: HashMap<String, String>,
+    #[serde(with = "pairs")]
     lessons: HashMap<ChapterKey, Vec<String>>,
+    #[serde(with = "pairs")]
     lesson_ids: HashMap<LessonKey, String>,
+    raw_readmes: HashMap<String, String>,
+    #[serde(default)]
+    plain_readmes: HashMap<String, Vec<String>>, // Rendered, for indexing without pandoc
+    #[serde(default)]
+    course_opens: HashMap<String, CourseOpens>,
+    #[serde(default)]
+    courses_fetched: u64, // Unix seconds
+    #[serde(default)]
+    chapters_fetched: HashMap<String, u64>, // Course slug to Unix seconds
+    // Readmes set aside by a refresh, kept unless fetched again
+    #[serde(skip)]
+    stale_readmes: HashMap<String, String>,
+    #[serde(skip)]
     readmes: HashMap<String, String>,
+    #[serde(skip)]
+    indexes: HashMap<String, CourseIndex>,
 }
 
 impl Cache {
+    /// Load the persisted cache, or start empty if there is none
+    pub fn load() -> Result<Self, anyhow::Error> {
+        let Some(path) = cache_path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let contents =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents).context(format!("Invalid cache file {}", path.display()))
+    }
+
+    pub fn save(&mut self) -> Result<(), anyhow::Error> {
+        let path = cache_path().context("Failed to find cache directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        }
+
+        for (lesson_id, readme) in self.stale_readmes.drain() {
+            self.raw_readmes.entry(lesson_id).or_insert(readme);
+        }
+
+        // Write a copy first, so that a failed write can't leave half a cache
+        let contents = serde_json::to_string(self)?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, contents)
+            .context(format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path).context(format!("Failed to replace {}", path.display()))
+    }
+
+    /// Treat every list as stale and ignore cached readmes for this session,
+    /// without losing what isn't fetched again
+    pub fn refresh(&mut self) {
+        self.courses_fetched = 0;
+        self.chapters_fetched.clear();
+        self.stale_readmes = mem::take(&mut self.raw_readmes);
+        self.plain_readmes.clear();
+    }
+
+    /// Whether the course list is old enough to fetch again
+    #[must_use]
+    pub fn courses_stale(&self) -> bool {
+        is_stale(self.courses_fetched)
+    }
+
+    /// Whether a course's chapter and lesson lists are old enough to fetch again
+    #[must_use]
+    pub fn course_stale(&self, slug: &str) -> bool {
+        self.chapters_fetched
+            .get(slug)
+            .is_none_or(|&fetched| is_stale(fetched))
+    }
+
+    #[must_use]
     pub const fn courses(&self) -> Option<&Vec<(String, String)>> {
         self.courses.as_ref()
     }
 
     pub fn set_courses(&mut self, courses: Vec<(String, String)>) {
+        self.courses_fetched = now();
         self.courses = Some(courses);
     }
 
+    #[must_use]
     pub fn chapters(&self, slug: &str) -> Option<&Vec<String>> {
         self.chapters.get(slug)
     }
 
     pub fn set_chapters(&mut self, slug: String, chapters: Vec<String>) {
+        self.chapters_fetched.insert(slug.clone(), now());
         self.chapters.insert(slug, chapters);
     }
 
+    #[must_use]
     pub fn course_uuid(&self, slug: &str) -> Option<&String> {
         self.course_uuids.get(slug)
     }
@@ -48,6 +151,7 @@ impl Cache {
         self.course_uuids.insert(slug, uuid);
     }
 
+    #[must_use]
     pub fn lessons(&self, course_uuid: &str, chapter_no: usize) -> Option<&Vec<String>> {
         self.lessons.get(&ChapterKey {
             course_uuid: course_uuid.to_owned(),
@@ -65,6 +169,27 @@ impl Cache {
         );
     }
 
+    /// Replace every lesson list of a course, given each chapter's lesson IDs
+    /// and titles
+    pub fn set_all_lessons(&mut self, course_uuid: &str, chapters: Vec<Vec<(String, String)>>) {
+        // Chapters and lessons may have been removed since the last fetch
+        self.lessons.retain(|key, _| key.course_uuid != course_uuid);
+        self.lesson_ids
+            .retain(|key, _| key.course_uuid != course_uuid);
+        self.indexes.remove(course_uuid);
+
+        for (ch_idx, chapter) in chapters.into_iter().enumerate() {
+            let ch_no = ch_idx + 1;
+            let titles = chapter.iter().map(|(_, title)| title.clone()).collect();
+            self.set_lessons(course_uuid.to_owned(), ch_no, titles);
+
+            for (lesson_idx, (lesson_id, _)) in chapter.into_iter().enumerate() {
+                self.set_lesson_id(course_uuid.to_owned(), ch_no, lesson_idx + 1, lesson_id);
+            }
+        }
+    }
+
+    #[must_use]
     pub fn lesson_id(
         &self,
         course_uuid: &str,
@@ -95,6 +220,27 @@ impl Cache {
         );
     }
 
+    #[must_use]
+    pub fn raw_readme(&self, lesson_id: &str) -> Option<&String> {
+        self.raw_readmes.get(lesson_id)
+    }
+
+    pub fn set_raw_readme(&mut self, lesson_id: String, readme: String) {
+        // The readme may have changed since it was rendered
+        self.plain_readmes.remove(&lesson_id);
+        self.raw_readmes.insert(lesson_id, readme);
+    }
+
+    #[must_use]
+    pub fn plain_readme(&self, lesson_id: &str) -> Option<&Vec<String>> {
+        self.plain_readmes.get(lesson_id)
+    }
+
+    pub fn set_plain_readme(&mut self, lesson_id: String, lines: Vec<String>) {
+        self.plain_readmes.insert(lesson_id, lines);
+    }
+
+    #[must_use]
     pub fn readme(&self, lesson_id: &str) -> Option<&String> {
         self.readmes.get(lesson_id)
     }
@@ -102,4 +248,111 @@ impl Cache {
     pub fn set_readme(&mut self, lesson_id: String, readme: String) {
         self.readmes.insert(lesson_id, readme);
     }
+
+    pub fn record_course_open(&mut self, slug: &str) {
+        let opens = self.course_opens.entry(slug.to_owned()).or_default();
+        opens.count += 1;
+        opens.last_opened = now();
+    }
+
+    /// How often and how recently a course has been opened
+    #[must_use]
+    pub fn frecency(&self, slug: &str) -> u64 {
+        let Some(opens) = self.course_opens.get(slug) else {
+            return 0;
+        };
+
+        let age_days = now().saturating_sub(opens.last_opened) / 86_400;
+        let weight = match age_days {
+            0 => 8,
+            1..=6 => 4,
+            7..=29 => 2,
+            _ => 1,
+        };
+        opens.count * weight
+    }
+
+    /// Every lesson with a cached title, sorted by course and position
+    #[must_use]
+    pub fn cached_lessons(&self) -> Vec<CachedLesson<'_>> {
+        let mut lessons = Vec::new();
+
+        for (slug, uuid) in &self.course_uuids {
+            for (key, titles) in self.lessons.iter().filter(|(k, _)| &k.course_uuid == uuid) {
+                for (i, title) in titles.iter().enumerate() {
+                    let readme = self
+                        .lesson_id(uuid, key.chapter_no, i + 1)
+                        .and_then(|id| self.raw_readmes.get(id))
+                        .map(String::as_str);
+
+                    lessons.push(CachedLesson {
+                        course_slug: slug,
+                        chapter_no: key.chapter_no,
+                        lesson_no: i + 1,
+                        title,
+                        readme,
+                    });
+                }
+            }
+        }
+
+        lessons.sort_unstable_by(|a, b| {
+            (a.course_slug, a.chapter_no, a.lesson_no).cmp(&(
+                b.course_slug,
+                b.chapter_no,
+                b.lesson_no,
+            ))
+        });
+        lessons
+    }
+
+    #[must_use]
+    pub fn course_index(&self, course_uuid: &str) -> Option<&CourseIndex> {
+        self.indexes.get(course_uuid)
+    }
+
+    pub fn set_course_index(&mut self, course_uuid: String, index: CourseIndex) {
+        self.indexes.insert(course_uuid, index);
+    }
+}
+
+fn is_stale(fetched: u64) -> bool {
+    now().saturating_sub(fetched) > MAX_AGE_SECS
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn cache_path() -> Option<PathBuf> {
+    choose_base_strategy()
+        .ok()
+        .map(|strategy| strategy.cache_dir().join("vedtoob").join("cache.json"))
+}
+
+// JSON objects need string keys, so struct-keyed maps are stored as pairs
+mod pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::{collections::HashMap, hash::Hash};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs: Vec<(K, V)> = Vec::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
 }
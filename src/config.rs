@@ -0,0 +1,77 @@
+use crate::keymap::KeymapConfig;
+use anyhow::Context;
+use etcetera::{BaseStrategy, choose_base_strategy};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+// Settings from $XDG_CONFIG_HOME/vedtoob/config.toml (or platform equivalent)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub theme: Option<String>,
+    pub continuous_reading: bool, // Scroll on from one lesson into the next
+    pub preview: bool,            // Load the selection without pressing Enter
+    pub keys: KeymapConfig,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, anyhow::Error> {
+        let Some(path) = config_dir().map(|dir| dir.join("config.toml")) else {
+            return Ok(Self::default());
+        };
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .context(format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).context(format!("Invalid config file {}", path.display()))
+    }
+}
+
+// Widths of the side panes, changed with the resize keys and kept across sessions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneLayout {
+    pub chapters: PaneSize,
+    pub lessons: PaneSize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneSize {
+    pub width: Option<u16>, // None fits the titles
+    pub collapsed: bool,    // A thin strip with only the numbers
+}
+
+impl PaneLayout {
+    #[must_use]
+    pub fn load() -> Self {
+        layout_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        let path = layout_path().context("Failed to find config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        }
+
+        let contents = toml::to_string(self)?;
+        fs::write(&path, contents).context(format!("Failed to write {}", path.display()))
+    }
+}
+
+fn layout_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("layout.toml"))
+}
+
+#[must_use]
+pub fn config_dir() -> Option<PathBuf> {
+    choose_base_strategy()
+        .ok()
+        .map(|strategy| strategy.config_dir().join("vedtoob"))
+}
@@ -2,6 +2,19 @@ use anyhow::{Context, anyhow};
 use reqwest::blocking::get;
 use serde::Deserialize;
 use serde_json::Value;
+use std::fmt;
+
+// A lesson without a readme, which callers can skip over
+#[derive(Debug)]
+pub struct NoReadme;
+
+impl fmt::Display for NoReadme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No readme found in lesson data")
+    }
+}
+
+impl std::error::Error for NoReadme {}
 
 // Response types for /v1/static/courses/overview
 #[derive(Deserialize)]
@@ -136,6 +149,21 @@ pub fn get_lessons_by_course_id(
     Ok(titles)
 }
 
+// (UUID, title) of every lesson, by chapter
+pub fn get_all_lessons_by_course_id(
+    course_uuid: &str,
+) -> Result<Vec<Vec<(String, String)>>, anyhow::Error> {
+    let url = format!("https://api.boot.dev/v1/courses/{}", course_uuid);
+    let response: CourseResponse = get(url)?.json()?;
+
+    let chapters = response
+        .chapters
+        .into_iter()
+        .map(|ch| ch.lessons.into_iter().map(|l| (l.uuid, l.title)).collect())
+        .collect();
+    Ok(chapters)
+}
+
 pub fn get_readme_by_id(id: &str) -> Result<String, anyhow::Error> {
     let url = format!("https://api.boot.dev/v1/static/lessons/{}", id);
     let response: LessonResponse = get(url)?.json()?;
@@ -150,7 +178,7 @@ pub fn get_readme_by_id(id: &str) -> Result<String, anyhow::Error> {
         .find(|(k, _)| k.starts_with("LessonData"))
         .and_then(|(_, v)| v.get("Readme"))
         .and_then(|v| v.as_str())
-        .context("No readme found in lesson data")?;
+        .ok_or(NoReadme)?;
 
     Ok(readme.to_owned())
 }
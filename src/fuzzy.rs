@@ -0,0 +1,99 @@
+// Subsequence matching with fzf-style scoring: consecutive chars and chars
+// at word boundaries score higher, and gaps between matched chars cost a bit
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+const MAX_GAP_PENALTY: i64 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub indices: Vec<usize>, // Matched char indices in the text
+}
+
+/// Case-insensitive fuzzy match of `pattern` against `text`
+#[must_use]
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+
+    let original: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = original.iter().copied().map(fold).collect();
+
+    // Try every start position for the first char, keeping the best
+    (0..folded.len())
+        .filter(|&start| folded[start] == pattern[0])
+        .filter_map(|start| {
+            let indices = match_from(&folded, &pattern, start)?;
+            let score = score(&original, &indices);
+            Some(FuzzyMatch { score, indices })
+        })
+        .max_by_key(|m| m.score)
+}
+
+fn match_from(text: &[char], pattern: &[char], start: usize) -> Option<Vec<usize>> {
+    let mut indices = vec![start];
+    let mut pos = start + 1;
+
+    for &c in &pattern[1..] {
+        let offset = text[pos..].iter().position(|&t| t == c)?;
+        indices.push(pos + offset);
+        pos += offset + 1;
+    }
+
+    Some(indices)
+}
+
+fn score(text: &[char], indices: &[usize]) -> i64 {
+    let mut total = 0;
+    let mut prev: Option<usize> = None;
+
+    for &i in indices {
+        total += SCORE_MATCH;
+
+        if is_boundary(text, i) {
+            total += if i == 0 {
+                BONUS_BOUNDARY + BONUS_FIRST_CHAR
+            } else {
+                BONUS_BOUNDARY
+            };
+        }
+
+        if let Some(p) = prev {
+            if i == p + 1 {
+                total += BONUS_CONSECUTIVE;
+            } else {
+                let gap = i64::try_from(i - p - 1).unwrap_or(i64::MAX);
+                total -= (gap * PENALTY_GAP).min(MAX_GAP_PENALTY);
+            }
+        }
+
+        prev = Some(i);
+    }
+
+    total
+}
+
+fn is_boundary(text: &[char], i: usize) -> bool {
+    if i == 0 {
+        return true;
+    }
+    let (prev, cur) = (text[i - 1], text[i]);
+    !prev.is_alphanumeric() || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
@@ -0,0 +1,135 @@
+use anyhow::Context;
+use etcetera::{BaseStrategy, choose_base_strategy};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Insert(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    DeleteWord,
+    Clear,
+}
+
+/// Apply an edit to `text`, where `cursor` is a char index into it
+pub fn apply(text: &mut String, cursor: &mut usize, edit: Edit) {
+    let len = text.chars().count();
+    *cursor = (*cursor).min(len);
+
+    match edit {
+        Edit::Insert(c) => {
+            text.insert(byte_index(text, *cursor), c);
+            *cursor += 1;
+        }
+        Edit::Backspace if *cursor > 0 => {
+            *cursor -= 1;
+            text.remove(byte_index(text, *cursor));
+        }
+        Edit::Delete if *cursor < len => {
+            text.remove(byte_index(text, *cursor));
+        }
+        Edit::Left => *cursor = cursor.saturating_sub(1),
+        Edit::Right => *cursor = (*cursor + 1).min(len),
+        Edit::Home => *cursor = 0,
+        Edit::End => *cursor = len,
+        Edit::DeleteWord => {
+            // Like a shell: trailing spaces, then the word before them
+            let chars: Vec<char> = text.chars().collect();
+            let mut start = *cursor;
+            while start > 0 && chars[start - 1].is_whitespace() {
+                start -= 1;
+            }
+            while start > 0 && !chars[start - 1].is_whitespace() {
+                start -= 1;
+            }
+            text.replace_range(byte_index(text, start)..byte_index(text, *cursor), "");
+            *cursor = start;
+        }
+        Edit::Clear => {
+            text.clear();
+            *cursor = 0;
+        }
+        Edit::Backspace | Edit::Delete => {}
+    }
+}
+
+/// Display width of the text before the cursor, for placing it on screen
+#[must_use]
+pub fn cursor_x(text: &str, cursor: usize) -> u16 {
+    let before = &text[..byte_index(text, cursor)];
+    u16::try_from(before.width()).unwrap_or(u16::MAX)
+}
+
+fn byte_index(text: &str, cursor: usize) -> usize {
+    text.char_indices()
+        .nth(cursor)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+// Past search queries, oldest first, kept across sessions
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<String>,
+}
+
+impl History {
+    #[must_use]
+    pub fn load() -> Self {
+        history_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        let path = history_path().context("Failed to find data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context(format!("Failed to create {}", dir.display()))?;
+        }
+
+        let contents = serde_json::to_string(self)?;
+        fs::write(&path, contents).context(format!("Failed to write {}", path.display()))
+    }
+
+    pub fn push(&mut self, query: &str) {
+        if query.trim().is_empty() {
+            return;
+        }
+
+        self.entries.retain(|entry| entry != query);
+        self.entries.push(query.to_owned());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+    }
+
+    /// The entry `back` steps from the end, where 1 is the newest
+    #[must_use]
+    pub fn get(&self, back: usize) -> Option<&String> {
+        self.entries.get(self.entries.len().checked_sub(back)?)
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    choose_base_strategy()
+        .ok()
+        .map(|strategy| strategy.data_dir().join("vedtoob").join("history.json"))
+}
@@ -0,0 +1,515 @@
+use anyhow::{anyhow, bail};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    BackToCourses,
+    GoBack,
+    MoveUp,
+    MoveDown,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    Top,
+    Bottom,
+    Select,
+    Search,
+    SearchCourse,
+    FindLesson,
+    Toc,
+    NextMatch,
+    PrevMatch,
+    NextLesson,
+    PrevLesson,
+    ReadChapter,
+    ToggleZen,
+    WidenPane,
+    NarrowPane,
+    CollapsePane,
+    NextPane,
+    PrevPane,
+    FocusChapters,
+    FocusLessons,
+    FocusReadme,
+    Help,
+
+    // Typing a query
+    Cancel,
+    Submit,
+    HistoryPrev,
+    HistoryNext,
+    CursorLeft,
+    CursorRight,
+    CursorHome,
+    CursorEnd,
+    DeleteBack,
+    DeleteForward,
+    DeleteWord,
+    ClearLine,
+}
+
+impl Action {
+    /// What the action does, for the help overlay
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Quit => "Quit",
+            Self::BackToCourses => "Return to the courses list",
+            Self::GoBack => "Go back a pane",
+            Self::MoveUp => "Move up",
+            Self::MoveDown => "Move down",
+            Self::PageUp => "Page up",
+            Self::PageDown => "Page down",
+            Self::HalfPageUp => "Half a page up",
+            Self::HalfPageDown => "Half a page down",
+            Self::Top => "Go to the top",
+            Self::Bottom => "Go to the bottom",
+            Self::Select => "Select or open",
+            Self::Search => "Search or filter this pane",
+            Self::SearchCourse => "Search all lessons in the course",
+            Self::FindLesson => "Go to any lesson in the course",
+            Self::Toc => "Table of contents",
+            Self::NextMatch => "Next match",
+            Self::PrevMatch => "Previous match",
+            Self::NextLesson => "Next lesson (into the next chapter at the end)",
+            Self::PrevLesson => "Previous lesson",
+            Self::ReadChapter => "Read the whole chapter as one document",
+            Self::ToggleZen => "Toggle fullscreen reading",
+            Self::WidenPane => "Widen the pane (Lessons from the readme)",
+            Self::NarrowPane => "Narrow the pane (Lessons from the readme)",
+            Self::CollapsePane => "Collapse the pane to its numbers, or expand it",
+            Self::NextPane => "Focus the next pane",
+            Self::PrevPane => "Focus the previous pane",
+            Self::FocusChapters => "Focus the chapters",
+            Self::FocusLessons => "Focus the lessons",
+            Self::FocusReadme => "Focus the readme",
+            Self::Help => "Show this help",
+            Self::Cancel => "Cancel or close",
+            Self::Submit => "Finish or open",
+            Self::HistoryPrev => "Previous query in history",
+            Self::HistoryNext => "Next query in history",
+            Self::CursorLeft => "Cursor left",
+            Self::CursorRight => "Cursor right",
+            Self::CursorHome => "Cursor to start",
+            Self::CursorEnd => "Cursor to end",
+            Self::DeleteBack => "Delete previous char",
+            Self::DeleteForward => "Delete next char",
+            Self::DeleteWord => "Delete previous word",
+            Self::ClearLine => "Clear the query",
+        }
+    }
+
+    /// Heading the action is listed under in the help overlay
+    #[must_use]
+    pub const fn category(self) -> &'static str {
+        match self {
+            Self::MoveUp
+            | Self::MoveDown
+            | Self::PageUp
+            | Self::PageDown
+            | Self::HalfPageUp
+            | Self::HalfPageDown
+            | Self::Top
+            | Self::Bottom => "Moving",
+            Self::Select
+            | Self::GoBack
+            | Self::BackToCourses
+            | Self::Toc
+            | Self::NextLesson
+            | Self::PrevLesson
+            | Self::ReadChapter
+            | Self::ToggleZen => "Opening",
+            Self::Search
+            | Self::NextMatch
+            | Self::PrevMatch
+            | Self::SearchCourse
+            | Self::FindLesson => "Searching",
+            Self::WidenPane
+            | Self::NarrowPane
+            | Self::CollapsePane
+            | Self::NextPane
+            | Self::PrevPane
+            | Self::FocusChapters
+            | Self::FocusLessons
+            | Self::FocusReadme => "Layout",
+            Self::Quit | Self::Help => "General",
+            Self::Cancel
+            | Self::Submit
+            | Self::HistoryPrev
+            | Self::HistoryNext
+            | Self::CursorLeft
+            | Self::CursorRight
+            | Self::CursorHome
+            | Self::CursorEnd
+            | Self::DeleteBack
+            | Self::DeleteForward
+            | Self::DeleteWord
+            | Self::ClearLine => "While typing a search",
+        }
+    }
+}
+
+// Keys are looked up in the most specific mode first, so e.g. `readme`
+// bindings win over `normal` ones while the Readme pane is active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Normal,
+    CourseList,
+    Chapters,
+    Lessons,
+    Readme,
+    Search,
+    Picker,
+    Toc,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the char (e.g., 'G'), and of BackTab
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers.difference(KeyModifiers::SHIFT),
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse keys like "j", "G", "enter", "pagedown", "ctrl-d" or "alt-shift-up"
+    pub fn parse(text: &str) -> Result<Self, anyhow::Error> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+
+        // A trailing "-" is the minus key, not a separator
+        while let Some((modifier, tail)) = rest.split_once('-')
+            && !tail.is_empty()
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier \"{}\" in key \"{}\"", modifier, text),
+            };
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => bail!("Unknown key \"{}\"", text),
+                },
+            },
+        };
+
+        // Shift-Tab arrives as BackTab
+        if code == KeyCode::Tab && modifiers.contains(KeyModifiers::SHIFT) {
+            return Ok(Self::new(KeyCode::BackTab, modifiers));
+        }
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+// Short names for the status bar, e.g. "^D" or "PgUp"
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "^")?;
+            if let KeyCode::Char(c) = self.code {
+                return write!(f, "{}", c.to_ascii_uppercase());
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            KeyCode::Backspace => write!(f, "Bksp"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+// One key or a list of them, as written in the config file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Overrides from the `[keys.<mode>]` tables of the config file
+pub type KeymapConfig = HashMap<Mode, HashMap<Action, KeyList>>;
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Mode, Vec<(Action, Vec<Key>)>>, // In help order
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = HashMap::new();
+        for (mode, defaults) in DEFAULTS.iter().chain(SHARED_DEFAULTS) {
+            let parsed = defaults.iter().map(|(action, keys)| {
+                let keys = keys
+                    .iter()
+                    .map(|k| Key::parse(k).expect("default keys should parse"))
+                    .collect();
+                (*action, keys)
+            });
+            bindings
+                .entry(*mode)
+                .or_insert_with(Vec::new)
+                .extend(parsed);
+        }
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The default keymap, with the keys for each configured action replaced
+    pub fn new(config: &KeymapConfig) -> Result<Self, anyhow::Error> {
+        let mut keymap = Self::default();
+
+        for (mode, overrides) in config {
+            let bindings = keymap.bindings.entry(*mode).or_default();
+
+            for (action, keys) in overrides {
+                let keys = match keys {
+                    KeyList::One(key) => vec![Key::parse(key)],
+                    KeyList::Many(keys) => keys.iter().map(|k| Key::parse(k)).collect(),
+                }
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| anyhow!("Invalid keys for {:?} in {:?} mode: {}", action, mode, e))?;
+
+                match bindings.iter_mut().find(|(a, _)| a == action) {
+                    Some((_, existing)) => *existing = keys,
+                    None => bindings.push((*action, keys)),
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// The action bound to a key, trying each mode in turn
+    #[must_use]
+    pub fn action(&self, modes: &[Mode], key: KeyEvent) -> Option<Action> {
+        let key = Key::from(key);
+        modes.iter().find_map(|mode| {
+            self.bindings
+                .get(mode)?
+                .iter()
+                .find_map(|(action, keys)| keys.contains(&key).then_some(*action))
+        })
+    }
+
+    /// Keys bound to an action, from the first mode that binds it
+    #[must_use]
+    pub fn keys(&self, modes: &[Mode], action: Action) -> &[Key] {
+        modes
+            .iter()
+            .find_map(|mode| {
+                self.bindings
+                    .get(mode)?
+                    .iter()
+                    .find(|(a, _)| *a == action)
+                    .map(|(_, keys)| keys.as_slice())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every action bound in the given modes, with its keys, taking each
+    /// action from the first mode that binds it
+    #[must_use]
+    pub fn bindings(&self, modes: &[Mode]) -> Vec<(Action, &[Key])> {
+        let mut bindings: Vec<(Action, &[Key])> = Vec::new();
+
+        for mode in modes {
+            for (action, keys) in self.bindings.get(mode).into_iter().flatten() {
+                if !keys.is_empty() && !bindings.iter().any(|(a, _)| a == action) {
+                    bindings.push((*action, keys));
+                }
+            }
+        }
+
+        bindings
+    }
+
+    /// Help like " q: quit | j/k: down/up ", showing the first key of each
+    /// action and leaving out entries with unbound actions
+    #[must_use]
+    pub fn help(&self, modes: &[Mode], entries: &[(&[Action], &str)]) -> String {
+        let parts: Vec<String> = entries
+            .iter()
+            .filter_map(|(actions, label)| {
+                let keys: Option<Vec<String>> = actions
+                    .iter()
+                    .map(|&action| self.keys(modes, action).first().map(Key::to_string))
+                    .collect();
+                Some(format!("{}: {}", keys?.join("/"), label))
+            })
+            .collect();
+
+        format!(" {} ", parts.join(" | "))
+    }
+}
+
+// Default keys for each action in a mode
+type Defaults = &'static [(Action, &'static [&'static str])];
+
+const COURSE_CONTENT: Defaults = &[
+    (Action::SearchCourse, &["S"]),
+    (Action::FindLesson, &["ctrl-p"]),
+    (Action::ReadChapter, &["R"]),
+    (Action::WidenPane, &[">"]),
+    (Action::NarrowPane, &["<"]),
+    (Action::CollapsePane, &["z"]),
+    (Action::NextPane, &["tab"]),
+    (Action::PrevPane, &["shift-tab"]),
+    (Action::FocusChapters, &["alt-1"]),
+    (Action::FocusLessons, &["alt-2"]),
+    (Action::FocusReadme, &["alt-3"]),
+];
+
+// Keys a mode takes on after its own defaults
+const SHARED_DEFAULTS: &[(Mode, Defaults)] = &[(Mode::Readme, COURSE_CONTENT)];
+
+const DEFAULTS: &[(Mode, Defaults)] = &[
+    (
+        Mode::Normal,
+        &[
+            (Action::Quit, &["q"]),
+            (Action::BackToCourses, &["esc"]),
+            (Action::GoBack, &["h", "left"]),
+            (Action::Select, &["l", "enter", "right"]),
+            (Action::MoveDown, &["j", "down"]),
+            (Action::MoveUp, &["k", "up"]),
+            (Action::PageDown, &["pagedown"]),
+            (Action::PageUp, &["pageup"]),
+            (Action::HalfPageDown, &["ctrl-d"]),
+            (Action::HalfPageUp, &["ctrl-u"]),
+            (Action::Top, &["g", "home"]),
+            (Action::Bottom, &["G", "end"]),
+            (Action::Search, &["/"]),
+            (Action::Help, &["?"]),
+        ],
+    ),
+    (Mode::CourseList, &[]),
+    (Mode::Chapters, COURSE_CONTENT),
+    (Mode::Lessons, COURSE_CONTENT),
+    // Plus COURSE_CONTENT, from SHARED_DEFAULTS
+    (
+        Mode::Readme,
+        &[
+            (Action::NextMatch, &["n"]),
+            (Action::PrevMatch, &["N"]),
+            (Action::Toc, &["t"]),
+            (Action::NextLesson, &["]"]),
+            (Action::PrevLesson, &["["]),
+            (Action::ToggleZen, &["f"]),
+        ],
+    ),
+    (
+        Mode::Search,
+        &[
+            (Action::Cancel, &["esc"]),
+            (Action::Submit, &["enter"]),
+            (Action::HistoryPrev, &["up"]),
+            (Action::HistoryNext, &["down"]),
+            (Action::CursorLeft, &["left"]),
+            (Action::CursorRight, &["right"]),
+            (Action::CursorHome, &["home"]),
+            (Action::CursorEnd, &["end"]),
+            (Action::DeleteBack, &["backspace"]),
+            (Action::DeleteForward, &["delete"]),
+            (Action::DeleteWord, &["ctrl-w"]),
+            (Action::ClearLine, &["ctrl-u"]),
+        ],
+    ),
+    (
+        Mode::Picker,
+        &[
+            (Action::Cancel, &["esc"]),
+            (Action::Submit, &["enter"]),
+            (Action::MoveUp, &["up", "ctrl-p"]),
+            (Action::MoveDown, &["down", "ctrl-n"]),
+            (Action::DeleteBack, &["backspace"]),
+        ],
+    ),
+    (
+        Mode::Toc,
+        &[
+            (Action::Quit, &["q"]),
+            (Action::Cancel, &["esc", "t"]),
+            (Action::Select, &["enter", "right", "l"]),
+            (Action::MoveDown, &["j", "down"]),
+            (Action::MoveUp, &["k", "up"]),
+        ],
+    ),
+    (
+        Mode::Help,
+        &[
+            (Action::Quit, &["q"]),
+            (Action::Cancel, &["esc", "?"]),
+            (Action::MoveDown, &["j", "down"]),
+            (Action::MoveUp, &["k", "up"]),
+            (Action::PageDown, &["pagedown", "ctrl-d"]),
+            (Action::PageUp, &["pageup", "ctrl-u"]),
+        ],
+    ),
+];
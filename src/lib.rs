@@ -7,23 +7,37 @@
 )]
 
 pub mod app;
-mod cache;
+pub mod cache;
+pub mod config;
 mod fetch;
+pub mod fuzzy;
+pub mod input;
+pub mod keymap;
+mod math;
+pub mod mouse;
 pub mod nav;
+pub mod preview;
+pub mod search;
+pub mod theme;
+pub mod toc;
 pub mod ui;
+pub mod wrap;
 
+use ansi_to_tui::IntoText;
 use anyhow::Context;
 use bat::PrettyPrinter;
 use std::io::Write;
 use std::process::{Command, Stdio};
+use theme::Theme;
 
-pub fn highlight(content: &str, language: &str) -> Result<String, anyhow::Error> {
+pub fn highlight(content: &str, language: &str, theme: &Theme) -> Result<String, anyhow::Error> {
     let mut output = String::new();
 
     PrettyPrinter::new()
         .input_from_bytes(content.as_bytes())
         .language(language)
-        .colored_output(true)
+        .theme(&theme.syntax)
+        .colored_output(theme.color)
         .grid(false)
         .header(false)
         .line_numbers(false)
@@ -33,6 +47,20 @@ pub fn highlight(content: &str, language: &str) -> Result<String, anyhow::Error>
     Ok(output)
 }
 
+/// Lines of an ANSI-highlighted string, without the escape codes
+#[must_use]
+pub fn plain_lines(highlighted: &str) -> Vec<String> {
+    highlighted.as_bytes().into_text().map_or_else(
+        |_| highlighted.lines().map(str::to_owned).collect(),
+        |text| {
+            text.lines
+                .iter()
+                .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+                .collect()
+        },
+    )
+}
+
 #[must_use]
 pub fn pandoc_available() -> bool {
     Command::new("pandoc")
@@ -7,25 +7,93 @@ compile_error!("Choose exactly one TLS backend: `tls-rustls` or `tls-native`");
 #[cfg(not(any(feature = "tls-rustls", feature = "tls-native")))]
 compile_error!("Choose exactly one TLS backend: `tls-rustls` or `tls-native`");
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use ratatui::{
     DefaultTerminal,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+            KeyModifiers,
+        },
+        execute,
+    },
 };
 use std::io;
-use vedtoob::{app::App, nav::Navigation, pandoc_available, ui};
+use vedtoob::{
+    app::{App, Options, Overlay, Pane, View},
+    cache::Cache,
+    config::{Config, PaneLayout},
+    input::{Edit, History},
+    keymap::{Action, Keymap},
+    mouse::MouseInput,
+    nav::Navigation,
+    pandoc_available,
+    search::{Pattern, search_cache},
+    theme::Theme,
+    ui,
+};
 
 #[derive(Parser, Debug)]
 #[command(
     name = "vedtoob",
     version,
     about = "TUI browser for Boot.dev courses",
-    long_about = "TUI browser for Boot.dev courses\n\nControls:\n  q: quit\n  Esc: return to courses list\n  /: search courses\n  Enter: select\n  h/l: back/forward\n  j/k: down/up\n\nDependencies:\n  pandoc (required on PATH)\n  network access to api.boot.dev"
+    long_about = "TUI browser for Boot.dev courses\n\nControls (defaults; remap them in config.toml):\n  q: quit\n  ?: help\n  Esc: return to courses list\n  /: search courses (filter chapters/lessons, or lesson text in readme)\n  n/N: next/prev match (in readme)\n  ]/[: next/prev lesson (in readme)\n  S: search all lessons in course\n  Ctrl-p: go to any lesson in course\n  R: read whole chapter\n  Enter: select\n  h/l: back/forward\n  j/k: down/up (with a count, e.g. 5j)\n  PageUp/PageDown, Ctrl-u/Ctrl-d: page, half page\n  g/G (Home/End): top/bottom\n  t: table of contents (in readme)\n  f: fullscreen readme\n  </>: narrow/widen pane, z: collapse it\n  Tab/Shift-Tab, Alt-1/2/3: focus next/prev pane, or chapters/lessons/readme\n  Mouse: click to select or focus, double-click to open, wheel to scroll\n\nDependencies:\n  pandoc (required on PATH)\n  network access to api.boot.dev"
 )]
-struct Cli {}
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Fetch courses, lessons, and readmes anew instead of reading them from the cache
+    #[arg(long)]
+    refresh: bool,
+
+    /// Color theme: "dark", "light", "mono", a theme name from the config
+    /// directory's themes/, or a path to a theme file
+    #[arg(long)]
+    theme: Option<String>,
+
+    /// Disable colors (high-contrast mode); also enabled by `NO_COLOR`
+    #[arg(long)]
+    no_color: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Search lesson titles and readmes in the cache (case-insensitive unless --regex)
+    Search {
+        query: String,
+
+        /// Only search the course with this slug
+        #[arg(long, value_name = "SLUG")]
+        course: Option<String>,
+
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
 
 fn main() -> io::Result<()> {
-    let _cli = Cli::parse();
+    let cli = Cli::parse();
+
+    if let Some(Command::Search {
+        query,
+        course,
+        regex,
+        json,
+    }) = cli.command
+    {
+        let found = search(&query, course.as_deref(), regex, json).unwrap_or_else(|e| {
+            eprintln!("Error: {e:#}");
+            std::process::exit(2);
+        });
+        std::process::exit(i32::from(!found));
+    }
 
     if !pandoc_available() {
         eprintln!("Error: pandoc is required but not found in PATH");
@@ -33,43 +101,263 @@ fn main() -> io::Result<()> {
         std::process::exit(1);
     }
 
-    let mut terminal = ratatui::init();
-    let result = run(&mut terminal);
-    ratatui::restore();
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    });
+
+    // NO_COLOR yields to an explicit --theme, but not to the config file
+    let env_no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let theme_name = if cli.no_color || (cli.theme.is_none() && env_no_color) {
+        String::from("mono")
+    } else {
+        cli.theme
+            .or(config.theme)
+            .unwrap_or_else(|| String::from("dark"))
+    };
+    let theme = Theme::load(&theme_name).unwrap_or_else(|e| {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    });
+
+    let keymap = Keymap::new(&config.keys).unwrap_or_else(|e| {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    });
+
+    // A cache that can't be read isn't overwritten, so that nothing is lost
+    let (mut cache, load_error) = match Cache::load() {
+        Ok(cache) => (cache, None),
+        Err(e) => (Cache::default(), Some(e)),
+    };
+    if cli.refresh {
+        cache.refresh();
+    }
+
+    let (terminal_guard, mut terminal) = TerminalGuard::init()?;
+    let mut app = App::new(theme, keymap, cache, History::load());
+    app.options = Options {
+        continuous_reading: config.continuous_reading,
+        preview: config.preview,
+    };
+    app.layout = PaneLayout::load();
+    let result = run(&mut terminal, &mut app);
+    drop(terminal_guard);
+
+    if let Some(e) = load_error {
+        eprintln!("Error loading cache, so it wasn't saved: {e:#}");
+    } else if let Err(e) = app.cache.save() {
+        eprintln!("Error saving cache: {e:#}");
+    }
+    if let Err(e) = app.history.save() {
+        eprintln!("Error saving search history: {e:#}");
+    }
+    if let Err(e) = app.layout.save() {
+        eprintln!("Error saving pane layout: {e:#}");
+    }
+
     result
 }
 
-fn run(terminal: &mut DefaultTerminal) -> io::Result<()> {
-    let mut app = App::new();
+// Restores the terminal when dropped, also when unwinding from a panic or
+// returning early with an error
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn init() -> io::Result<(Self, DefaultTerminal)> {
+        let terminal = ratatui::init();
+        let guard = Self;
+        execute!(io::stdout(), EnableMouseCapture)?;
+        Ok((guard, terminal))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        ratatui::restore();
+    }
+}
+
+// Prints grep-like results, returning whether anything matched
+fn search(
+    query: &str,
+    course: Option<&str>,
+    regex: bool,
+    json: bool,
+) -> Result<bool, anyhow::Error> {
+    let pattern = Pattern::new(query, regex)?;
+    let hits = search_cache(&Cache::load()?, &pattern, course);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+    } else {
+        for hit in &hits {
+            let location = format!("{}:{}.{}", hit.course, hit.chapter, hit.lesson);
+            match hit.line {
+                Some(line) => println!("{}:{}: {}: {}", location, line, hit.title, hit.text),
+                None => println!("{}: {}", location, hit.title),
+            }
+        }
+    }
 
+    Ok(!hits.is_empty())
+}
+
+fn run(terminal: &mut DefaultTerminal, app: &mut App) -> io::Result<()> {
     loop {
-        terminal.draw(|frame| ui::render(&mut app, frame))?;
+        app.poll_preview();
+        app.poll_indexing();
+        terminal.draw(|frame| ui::render(app, frame))?;
+
+        // Only wait as long as a pending preview or indexing allows
+        let timeout = [app.preview_timeout(), app.indexing_timeout()]
+            .into_iter()
+            .flatten()
+            .min();
+        if let Some(timeout) = timeout
+            && !event::poll(timeout)?
+        {
+            continue;
+        }
 
-        if let Event::Key(key) = event::read()?
+        let event = event::read()?;
+        if let Event::Mouse(mouse) = event {
+            app.handle_mouse(mouse);
+        } else if let Event::Key(key) = event
             && key.kind == KeyEventKind::Press
+            && handle_key(app, key)
         {
-            if app.is_search_mode {
-                match key.code {
-                    KeyCode::Esc => app.exit_search(),
-                    KeyCode::Enter => app.submit_search(),
-                    KeyCode::Backspace => app.pop_search(),
-                    KeyCode::Char(c) => app.append_search(c),
-                    _ => {}
-                }
-            } else {
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Esc => app.back_to_courses(),
-                    KeyCode::Left | KeyCode::Char('h') => app.go_back(),
-                    KeyCode::Up | KeyCode::Char('k') => app.move_up(),
-                    KeyCode::Down | KeyCode::Char('j') => app.move_down(),
-                    KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => app.select(),
-                    KeyCode::Char('/') => app.enter_search(),
-                    _ => {}
-                }
-            }
+            break;
         }
+        app.schedule_preview();
     }
 
     Ok(())
 }
+
+// Returns whether to quit
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    let action = app.keymap.action(app.key_modes(), key);
+    // Unbound keys type into queries, but Ctrl combinations never do
+    let typed = match key.code {
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(c),
+        _ => None,
+    };
+
+    match app.overlay {
+        Overlay::Help => match action {
+            Some(Action::Quit) => return true,
+            Some(Action::Cancel) => app.close_help(),
+            Some(Action::MoveDown) => app.help_down(1),
+            Some(Action::MoveUp) => app.help_up(1),
+            Some(Action::PageDown) => app.help_down(app.help_page_size()),
+            Some(Action::PageUp) => app.help_up(app.help_page_size()),
+            _ => {}
+        },
+        Overlay::Search => match (action, typed) {
+            (Some(Action::Cancel), _) => app.exit_search(),
+            (Some(Action::Submit), _) => app.submit_search(),
+            (Some(Action::HistoryPrev), _) => app.history_prev(),
+            (Some(Action::HistoryNext), _) => app.history_next(),
+            (Some(Action::DeleteBack), _) => app.edit_search(Edit::Backspace),
+            (Some(Action::DeleteForward), _) => app.edit_search(Edit::Delete),
+            (Some(Action::CursorLeft), _) => app.edit_search(Edit::Left),
+            (Some(Action::CursorRight), _) => app.edit_search(Edit::Right),
+            (Some(Action::CursorHome), _) => app.edit_search(Edit::Home),
+            (Some(Action::CursorEnd), _) => app.edit_search(Edit::End),
+            (Some(Action::DeleteWord), _) => app.edit_search(Edit::DeleteWord),
+            (Some(Action::ClearLine), _) => app.edit_search(Edit::Clear),
+            (None, Some(c)) => app.edit_search(Edit::Insert(c)),
+            _ => {}
+        },
+        Overlay::CourseSearch => match (action, typed) {
+            (Some(Action::Cancel), _) => app.close_course_search(),
+            (Some(Action::Submit), _) => app.open_course_hit(),
+            (Some(Action::MoveUp), _) => app.course_hit_up(),
+            (Some(Action::MoveDown), _) => app.course_hit_down(),
+            (Some(Action::DeleteBack), _) => app.pop_course_search(),
+            (None, Some(c)) => app.append_course_search(c),
+            _ => {}
+        },
+        Overlay::LessonFinder => match (action, typed) {
+            (Some(Action::Cancel), _) => app.close_lesson_finder(),
+            (Some(Action::Submit), _) => app.open_lesson_finder_selection(),
+            (Some(Action::MoveUp), _) => app.lesson_finder_up(),
+            (Some(Action::MoveDown), _) => app.lesson_finder_down(),
+            (Some(Action::DeleteBack), _) => app.pop_lesson_finder(),
+            (None, Some(c)) => app.append_lesson_finder(c),
+            _ => {}
+        },
+        Overlay::Toc => match action {
+            Some(Action::Quit) => return true,
+            Some(Action::Cancel) => app.close_toc(),
+            Some(Action::MoveUp) => app.toc_up(),
+            Some(Action::MoveDown) => app.toc_down(),
+            Some(Action::Select) => app.toc_jump(),
+            _ => {}
+        },
+        Overlay::None => return handle_normal_key(app, action, typed),
+    }
+
+    false
+}
+
+fn handle_normal_key(app: &mut App, action: Option<Action>, typed: Option<char>) -> bool {
+    // A count (e.g., 5j) repeats the motion that follows it
+    if action.is_none()
+        && let Some(c @ '0'..='9') = typed
+        && (c != '0' || app.pending_count.is_some())
+    {
+        app.push_count_digit(c.to_digit(10).unwrap_or(0));
+        return false;
+    }
+    let count = app.take_count();
+
+    match action {
+        Some(Action::Quit) => return true,
+        Some(Action::BackToCourses) => app.back_to_courses(),
+        Some(Action::GoBack) => app.go_back(),
+        Some(Action::MoveUp) => app.move_up(count),
+        Some(Action::MoveDown) => app.move_down(count),
+        Some(Action::PageUp) => app.page_up(count),
+        Some(Action::PageDown) => app.page_down(count),
+        Some(Action::HalfPageUp) => app.half_page_up(count),
+        Some(Action::HalfPageDown) => app.half_page_down(count),
+        Some(Action::Top) => app.move_to_top(),
+        Some(Action::Bottom) => app.move_to_bottom(),
+        Some(Action::Select) => app.select(),
+        Some(Action::Search) => app.enter_search(),
+        Some(Action::Help) => app.open_help(),
+        Some(Action::FindLesson) if app.view == View::CourseContent => {
+            app.open_lesson_finder();
+        }
+        Some(Action::SearchCourse) if app.view == View::CourseContent => {
+            app.open_course_search();
+        }
+        Some(Action::ReadChapter) if app.view == View::CourseContent => app.read_chapter(),
+        Some(Action::ToggleZen) if app.in_readme() => app.toggle_zen(),
+        Some(Action::WidenPane) if app.view == View::CourseContent => app.widen_pane(count),
+        Some(Action::NarrowPane) if app.view == View::CourseContent => app.narrow_pane(count),
+        Some(Action::CollapsePane) if app.view == View::CourseContent => app.toggle_collapse(),
+        Some(Action::NextPane) if app.view == View::CourseContent => app.cycle_pane(true),
+        Some(Action::PrevPane) if app.view == View::CourseContent => app.cycle_pane(false),
+        Some(Action::FocusChapters) if app.view == View::CourseContent => {
+            app.focus_pane(Pane::Chapters);
+        }
+        Some(Action::FocusLessons) if app.view == View::CourseContent => {
+            app.focus_pane(Pane::Lessons);
+        }
+        Some(Action::FocusReadme) if app.view == View::CourseContent => {
+            app.focus_pane(Pane::Readme);
+        }
+        Some(Action::Toc) if app.in_readme() => app.open_toc(),
+        Some(Action::NextMatch) if app.in_readme() => app.next_match(),
+        Some(Action::PrevMatch) if app.in_readme() => app.prev_match(),
+        Some(Action::NextLesson) if app.in_readme() => app.next_lesson(),
+        Some(Action::PrevLesson) if app.in_readme() => app.prev_lesson(),
+        _ => {}
+    }
+
+    false
+}
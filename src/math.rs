@@ -0,0 +1,767 @@
+use crate::wrap::CENTER_MARK;
+
+// Info string for fenced blocks holding converted display math, so that
+// pandoc leaves them alone until we mark them for centering afterwards
+const DISPLAY_INFO: &str = "texmath";
+
+// Opening and closing delimiters of display math
+const DISPLAY_DELIMITERS: &[(&str, &str)] = &[("$$", "$$"), ("\\[", "\\]")];
+
+/// Convert `$...$`, `\(...\)`, `$$...$$`, and `\[...\]` math in markdown to
+/// Unicode approximations
+#[must_use]
+pub fn render_math(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    // Display math so far, with its delimiters, while it spans several lines
+    let mut display: Option<(String, (&str, &str))> = None;
+
+    for line in markdown.lines() {
+        if let Some((buf, (_, close))) = display.as_mut() {
+            if let Some(end) = line.find(*close) {
+                let rest = &line[end + close.len()..];
+                buf.push_str(&line[..end]);
+                push_display(&mut out, buf);
+                display = None;
+
+                if !rest.trim().is_empty() {
+                    out.push_str(&render_inline(rest));
+                    out.push('\n');
+                }
+            } else {
+                buf.push_str(line);
+                buf.push('\n');
+            }
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        let opened = DISPLAY_DELIMITERS.iter().find_map(|&(open, close)| {
+            trimmed.strip_prefix(open).map(|rest| (rest, (open, close)))
+        });
+        if let Some((rest, delimiters @ (_, close))) = opened {
+            if let Some(end) = rest.find(close) {
+                push_display(&mut out, &rest[..end]);
+
+                let after = &rest[end + close.len()..];
+                if !after.trim().is_empty() {
+                    out.push_str(&render_inline(after));
+                    out.push('\n');
+                }
+            } else {
+                display = Some((format!("{}\n", rest), delimiters));
+            }
+            continue;
+        }
+
+        out.push_str(&render_inline(line));
+        out.push('\n');
+    }
+
+    // Unterminated display math is left as it was
+    if let Some((buf, (open, _))) = display {
+        out.push_str(open);
+        out.push_str(&buf);
+    }
+
+    out
+}
+
+/// Replace converted display math blocks with their lines, marked to be
+/// centered in the readme pane when drawn
+#[must_use]
+pub fn mark_display_math(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut in_math = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if in_math {
+            if !trimmed.is_empty() && trimmed.chars().all(|c| c == '`') {
+                in_math = false;
+            } else {
+                out.push(CENTER_MARK);
+                out.push_str(trimmed);
+                out.push('\n');
+            }
+            continue;
+        }
+
+        if trimmed
+            .strip_prefix("```")
+            .is_some_and(|info| info.trim_start_matches('`').trim() == DISPLAY_INFO)
+        {
+            in_math = true;
+            continue;
+        }
+
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn push_display(out: &mut String, tex: &str) {
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+    out.push_str("```");
+    out.push_str(DISPLAY_INFO);
+    out.push('\n');
+
+    for row in tex.split("\\\\") {
+        let row = to_unicode(row);
+        if !row.is_empty() {
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+
+    out.push_str("```\n\n");
+}
+
+fn render_inline(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            // Leave code spans untouched
+            '`' => {
+                let ticks = chars[i..].iter().take_while(|&&c| c == '`').count();
+                let close = find_backticks(&chars, i + ticks, ticks);
+                let end = close.map_or(i + ticks, |c| c + ticks);
+                out.extend(&chars[i..end]);
+                i = end;
+            }
+            '\\' if chars.get(i + 1) == Some(&'$') => {
+                out.push_str("\\$");
+                i += 2;
+            }
+            '\\' if matches!(chars.get(i + 1), Some('(' | '[')) => {
+                let close = if chars[i + 1] == '(' { ')' } else { ']' };
+                if let Some(end) = find_str(&chars, i + 2, &['\\', close]) {
+                    let tex: String = chars[i + 2..end].iter().collect();
+                    out.push_str(&inline_math(&tex));
+                    i = end + 2;
+                } else {
+                    out.extend(&chars[i..i + 2]);
+                    i += 2;
+                }
+            }
+            '$' if chars.get(i + 1) == Some(&'$') => {
+                if let Some(end) = find_str(&chars, i + 2, &['$', '$']) {
+                    let tex: String = chars[i + 2..end].iter().collect();
+                    out.push_str(&inline_math(&tex));
+                    i = end + 2;
+                } else {
+                    out.push_str("$$");
+                    i += 2;
+                }
+            }
+            '$' => {
+                if let Some(end) = find_inline_close(&chars, i) {
+                    let tex: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&inline_math(&tex));
+                    i = end + 1;
+                } else {
+                    out.push('$');
+                    i += 1;
+                }
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+// Inline math goes through pandoc as markdown, so anything it could take for
+// markup (like the `_` of a fallback subscript such as _(bc)) goes in a code span
+fn inline_math(tex: &str) -> String {
+    let text = to_unicode(tex);
+    if !text.contains(['_', '*', '\\', '`']) {
+        return text;
+    }
+
+    // A fence longer than any run of backticks inside
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{0}{1}{2}{1}{0}", fence, pad, text)
+}
+
+fn find_backticks(chars: &[char], from: usize, count: usize) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if chars[i] == '`' {
+            let run = chars[i..].iter().take_while(|&&c| c == '`').count();
+            if run == count {
+                return Some(i);
+            }
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+fn find_str(chars: &[char], from: usize, needle: &[char]) -> Option<usize> {
+    (from..chars.len()).find(|&i| chars[i..].starts_with(needle))
+}
+
+// Follows pandoc's rules, so that prices like "$5 and $10" aren't math:
+// the opening `$` must be followed by a non-space, and the closing `$` must
+// be preceded by a non-space and not followed by a digit
+fn find_inline_close(chars: &[char], open: usize) -> Option<usize> {
+    let first = *chars.get(open + 1)?;
+    if first.is_whitespace() || first == '$' {
+        return None;
+    }
+
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '$' => {
+                let before = chars[i - 1];
+                let after = chars.get(i + 1);
+                if i > open + 1
+                    && !before.is_whitespace()
+                    && !after.is_some_and(char::is_ascii_digit)
+                {
+                    return Some(i);
+                }
+                return None;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn to_unicode(tex: &str) -> String {
+    let mut parser = Parser {
+        chars: tex.chars().collect(),
+        pos: 0,
+    };
+    let out = parser.parse_until(None);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn parse_until(&mut self, close: Option<char>) -> String {
+        let mut out = String::new();
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if Some(c) == close {
+                break;
+            }
+
+            match c {
+                '{' => out.push_str(&self.parse_until(Some('}'))),
+                '}' | '&' => {}
+                '~' | '\n' => out.push(' '),
+                '^' => {
+                    let arg = self.parse_arg();
+                    out.push_str(&script(&arg, superscript, '^'));
+                }
+                '_' => {
+                    let arg = self.parse_arg();
+                    out.push_str(&script(&arg, subscript, '_'));
+                }
+                '\\' => out.push_str(&self.parse_command()),
+                '\'' => out.push('′'),
+                '*' => out.push('∗'),
+                '-' => out.push('−'),
+                c => out.push(c),
+            }
+        }
+
+        out
+    }
+
+    fn parse_arg(&mut self) -> String {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.parse_until(Some('}'))
+            }
+            Some('\\') => {
+                self.pos += 1;
+                self.parse_command()
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => String::new(),
+        }
+    }
+
+    fn parse_optional_arg(&mut self) -> Option<String> {
+        if self.peek() != Some('[') {
+            return None;
+        }
+        self.pos += 1;
+        Some(self.parse_until(Some(']')))
+    }
+
+    fn parse_command(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+
+        if self.pos == start {
+            let Some(c) = self.peek() else {
+                return String::new();
+            };
+            self.pos += 1;
+            return match c {
+                ',' | ':' | ';' | ' ' | '\\' => String::from(" "),
+                '!' => String::new(),
+                c => c.to_string(),
+            };
+        }
+
+        let name: String = self.chars[start..self.pos].iter().collect();
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.parse_arg();
+                let denominator = self.parse_arg();
+                fraction(&numerator, &denominator)
+            }
+            "sqrt" => {
+                let index = self.parse_optional_arg();
+                let radicand = self.parse_arg();
+                root(index.as_deref(), &radicand)
+            }
+            "binom" => {
+                let n = self.parse_arg();
+                let k = self.parse_arg();
+                format!("C({}, {})", n, k)
+            }
+            "mathbb" => self.parse_arg().chars().map(blackboard).collect(),
+            "text" | "textrm" | "textit" | "textbf" | "mathrm" | "mathit" | "mathbf" | "mathsf"
+            | "mathtt" | "mathcal" | "operatorname" | "boldsymbol" => self.parse_arg(),
+            "bar" | "overline" => combine(&self.parse_arg(), '\u{0304}'),
+            "hat" | "widehat" => combine(&self.parse_arg(), '\u{0302}'),
+            "tilde" | "widetilde" => combine(&self.parse_arg(), '\u{0303}'),
+            "dot" => combine(&self.parse_arg(), '\u{0307}'),
+            "vec" => combine(&self.parse_arg(), '\u{20D7}'),
+            "begin" | "end" => {
+                self.parse_arg();
+                String::new()
+            }
+            "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" => {
+                if self.peek() == Some('.') {
+                    self.pos += 1;
+                }
+                String::new()
+            }
+            "quad" | "qquad" => String::from("  "),
+            // Named functions like \log and \max, or anything unknown
+            _ => symbol(&name).map_or_else(|| name.clone(), String::from),
+        }
+    }
+}
+
+fn fraction(numerator: &str, denominator: &str) -> String {
+    let vulgar = match (numerator.trim(), denominator.trim()) {
+        ("1", "2") => Some('½'),
+        ("1", "3") => Some('⅓'),
+        ("2", "3") => Some('⅔'),
+        ("1", "4") => Some('¼'),
+        ("3", "4") => Some('¾'),
+        ("1", "5") => Some('⅕'),
+        ("1", "6") => Some('⅙'),
+        ("1", "8") => Some('⅛'),
+        _ => None,
+    };
+
+    vulgar.map_or_else(
+        || format!("{}/{}", group(numerator), group(denominator)),
+        String::from,
+    )
+}
+
+fn root(index: Option<&str>, radicand: &str) -> String {
+    let sign = match index.map(str::trim) {
+        None | Some("" | "2") => String::from("√"),
+        Some("3") => String::from("∛"),
+        Some("4") => String::from("∜"),
+        Some(n) => format!("{}√", script(n, superscript, '^')),
+    };
+    format!("{}{}", sign, group(radicand))
+}
+
+// Parenthesize compound expressions
+fn group(expr: &str) -> String {
+    let expr = expr.trim();
+    let is_compound = expr.chars().any(|c| {
+        c.is_whitespace() || matches!(c, '+' | '−' | '±' | '·' | '×' | '/' | '=' | '<' | '>')
+    });
+
+    if is_compound {
+        format!("({})", expr)
+    } else {
+        expr.to_owned()
+    }
+}
+
+fn combine(base: &str, mark: char) -> String {
+    base.chars().flat_map(|c| [c, mark]).collect()
+}
+
+fn script(arg: &str, map: fn(char) -> Option<char>, marker: char) -> String {
+    let arg = arg.trim();
+    if arg.is_empty() {
+        return String::new();
+    }
+
+    arg.chars()
+        .map(map)
+        .collect::<Option<String>>()
+        .unwrap_or_else(|| {
+            if arg.chars().count() == 1 {
+                format!("{}{}", marker, arg)
+            } else {
+                format!("{}({})", marker, arg)
+            }
+        })
+}
+
+const fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '−' | '-' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'T' => 'ᵀ',
+        '′' | '∗' | '∘' => c,
+        _ => return None,
+    })
+}
+
+const fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '−' | '-' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+const fn blackboard(c: char) -> char {
+    match c {
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'C' => 'ℂ',
+        'P' => 'ℙ',
+        'E' => '𝔼',
+        c => c,
+    }
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    greek(name).or_else(|| operator(name))
+}
+
+fn greek(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        _ => return None,
+    })
+}
+
+fn operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        // Big operators
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+
+        // Relations
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "mid" => "∣",
+        "perp" => "⊥",
+
+        // Operators
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "circ" => "∘",
+        "oplus" => "⊕",
+        "otimes" => "⊗",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "lnot" | "neg" => "¬",
+        "mod" | "bmod" | "pmod" => "mod",
+
+        // Arrows
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+
+        // Miscellaneous
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "forall" => "∀",
+        "exists" => "∃",
+        "emptyset" | "varnothing" => "∅",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "prime" => "′",
+        "angle" => "∠",
+        "triangle" => "△",
+        "degree" => "°",
+        "ell" => "ℓ",
+        "hbar" => "ℏ",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "‖",
+
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_inline_math() {
+        assert_eq!(render_math("$x^2 + \\alpha$"), "x² + α\n");
+        assert_eq!(render_math("$\\frac{a+b}{2}$"), "(a+b)/2\n");
+        assert_eq!(render_math("$\\sqrt{x}$ and $\\sqrt[3]{8}$"), "√x and ∛8\n");
+        assert_eq!(render_math("\\(x_1\\) and \\[y^2\\]"), "x₁ and y²\n");
+    }
+
+    #[test]
+    fn leaves_prices_alone() {
+        assert_eq!(render_math("costs $5 and $10"), "costs $5 and $10\n");
+        assert_eq!(render_math("$ x$ and $x $"), "$ x$ and $x $\n");
+    }
+
+    #[test]
+    fn leaves_escaped_dollars_alone() {
+        assert_eq!(render_math("pay \\$5 or $x$"), "pay \\$5 or x\n");
+    }
+
+    #[test]
+    fn leaves_code_alone() {
+        assert_eq!(render_math("`$x^2$` and $x^2$"), "`$x^2$` and x²\n");
+        assert_eq!(render_math("```\n$x^2$\n```"), "```\n$x^2$\n```\n");
+    }
+
+    #[test]
+    fn falls_back_on_unknown_commands() {
+        assert_eq!(render_math("$\\foo x$"), "foo x\n");
+    }
+
+    #[test]
+    fn puts_markup_in_code_spans() {
+        // No subscript letters for b and c
+        assert_eq!(render_math("$a_{bc}$"), "`a_(bc)`\n");
+        assert_eq!(inline_math("`x`_y"), "`` `x`_y ``");
+    }
+
+    #[test]
+    fn marks_display_math_for_centering() {
+        let rendered = render_math("$$\n\\frac{1}{2}\n$$");
+        assert_eq!(rendered, "```texmath\n½\n```\n\n");
+        assert_eq!(mark_display_math(&rendered), "\u{2063}½\n\n");
+
+        let rendered = render_math("\\[\n\\sum_{i=1}^n i\n\\]");
+        assert_eq!(mark_display_math(&rendered), "\u{2063}∑ᵢ₌₁ⁿ i\n\n");
+    }
+
+    #[test]
+    fn splits_display_math_into_rows() {
+        let rendered = render_math("$$a \\\\ b$$ after $x$");
+        assert_eq!(
+            mark_display_math(&rendered),
+            "\u{2063}a\n\u{2063}b\n\n after x\n"
+        );
+    }
+
+    #[test]
+    fn keeps_unterminated_display_math() {
+        assert_eq!(render_math("$$\nx^2"), "$$\nx^2\n");
+        assert_eq!(render_math("\\[\nx^2"), "\\[\nx^2\n");
+    }
+}
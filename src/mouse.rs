@@ -0,0 +1,144 @@
+use crate::{
+    app::{App, Overlay, Pane, View},
+    nav::Navigation,
+};
+use ratatui::{
+    crossterm::event::{MouseButton, MouseEvent, MouseEventKind},
+    layout::{Margin, Position, Rect},
+};
+use std::time::{Duration, Instant};
+
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const WHEEL_STEP: usize = 3;
+
+// Where a click landed, so that a second one there counts as a double click
+#[derive(Debug, Clone, Copy)]
+pub struct Click {
+    at: Instant,
+    position: Position,
+}
+
+pub trait MouseInput {
+    fn handle_mouse(&mut self, event: MouseEvent);
+}
+
+impl MouseInput for App {
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        // Popups and typed input keep the focus
+        if self.overlay != Overlay::None {
+            return;
+        }
+
+        let position = Position::new(event.column, event.row);
+        let target = match self.view {
+            View::CourseList => self
+                .areas
+                .courses
+                .contains(position)
+                .then_some(self.active_pane),
+            View::CourseContent => self.pane_at(position),
+        };
+        let Some(pane) = target else {
+            return;
+        };
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.click(pane, position),
+            MouseEventKind::ScrollUp => self.scroll_pane(pane, |app| app.move_up(WHEEL_STEP)),
+            MouseEventKind::ScrollDown => {
+                self.scroll_pane(pane, |app| app.move_down(WHEEL_STEP));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl App {
+    fn pane_at(&self, position: Position) -> Option<Pane> {
+        [
+            (self.areas.chapters, Pane::Chapters),
+            (self.areas.lessons, Pane::Lessons),
+            (self.areas.readme, Pane::Readme),
+        ]
+        .into_iter()
+        .find(|(area, _)| area.contains(position))
+        .map(|(_, pane)| pane)
+    }
+
+    fn click(&mut self, pane: Pane, position: Position) {
+        let now = Instant::now();
+        let is_double = self.last_click.is_some_and(|click| {
+            click.position == position && now.duration_since(click.at) <= DOUBLE_CLICK
+        });
+        // A third click starts over
+        self.last_click = if is_double {
+            None
+        } else {
+            Some(Click { at: now, position })
+        };
+
+        if self.view == View::CourseList {
+            let len = self.get_filtered_courses().len();
+            let row = list_row(self.areas.courses, self.course_state.offset(), position);
+            if let Some(idx) = row.filter(|&idx| idx < len) {
+                self.course_state.select(Some(idx));
+                if is_double {
+                    self.select();
+                }
+            }
+            return;
+        }
+
+        match pane {
+            Pane::Chapters => {
+                self.active_pane = Pane::Chapters;
+                let len = self.filtered_chapters().len();
+                let row = list_row(self.areas.chapters, self.chapter_state.offset(), position);
+                if let Some(idx) = row.filter(|&idx| idx < len) {
+                    self.chapter_state.select(Some(idx));
+                    if is_double {
+                        self.select();
+                    }
+                }
+            }
+            Pane::Lessons => {
+                if self.lessons.is_empty() {
+                    return;
+                }
+                self.active_pane = Pane::Lessons;
+                let len = self.filtered_lessons().len();
+                let row = list_row(self.areas.lessons, self.lesson_state.offset(), position);
+                if let Some(idx) = row.filter(|&idx| idx < len) {
+                    self.lesson_state.select(Some(idx));
+                    if is_double {
+                        self.select();
+                    }
+                }
+            }
+            Pane::Readme => {
+                if !self.readme.is_empty() {
+                    self.active_pane = Pane::Readme;
+                }
+            }
+        }
+    }
+
+    // Scroll the pane under the pointer without moving the focus to it
+    fn scroll_pane(&mut self, pane: Pane, scroll: impl FnOnce(&mut Self)) {
+        let active = self.active_pane;
+        self.active_pane = pane;
+        scroll(self);
+        self.active_pane = active;
+    }
+}
+
+// Index of the list item at the given position, inside the block's borders
+fn list_row(area: Rect, offset: usize, position: Position) -> Option<usize> {
+    let inner = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    inner
+        .contains(position)
+        .then(|| offset + usize::from(position.y - inner.y))
+}
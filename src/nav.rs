@@ -1,82 +1,119 @@
 use crate::app::{App, Pane, View};
 
 pub trait Navigation {
-    fn move_up(&mut self);
-    fn move_down(&mut self);
+    fn move_up(&mut self, n: usize);
+    fn move_down(&mut self, n: usize);
+    fn move_to_top(&mut self);
+    fn move_to_bottom(&mut self);
+    fn page_size(&self) -> usize;
     fn select(&mut self);
     fn go_back(&mut self);
     fn back_to_courses(&mut self);
+
+    fn page_up(&mut self, n: usize) {
+        self.move_up(n.saturating_mul(self.page_size()));
+    }
+
+    fn page_down(&mut self, n: usize) {
+        self.move_down(n.saturating_mul(self.page_size()));
+    }
+
+    fn half_page_up(&mut self, n: usize) {
+        self.move_up(n.saturating_mul((self.page_size() / 2).max(1)));
+    }
+
+    fn half_page_down(&mut self, n: usize) {
+        self.move_down(n.saturating_mul((self.page_size() / 2).max(1)));
+    }
 }
 
 impl Navigation for App {
-    fn move_up(&mut self) {
+    fn move_up(&mut self, n: usize) {
         match self.view {
             View::CourseList => {
-                if let Some(idx) = self.course_state.selected()
-                    && idx > 0
-                {
-                    self.course_state.select(Some(idx - 1));
+                if let Some(idx) = self.course_state.selected() {
+                    self.course_state.select(Some(idx.saturating_sub(n)));
                 }
             }
             View::CourseContent => match self.active_pane {
                 Pane::Chapters => {
-                    if let Some(idx) = self.chapter_state.selected()
-                        && idx > 0
-                    {
-                        self.chapter_state.select(Some(idx - 1));
+                    if let Some(idx) = self.chapter_state.selected() {
+                        self.chapter_state.select(Some(idx.saturating_sub(n)));
                     }
                 }
                 Pane::Lessons => {
-                    if let Some(idx) = self.lesson_state.selected()
-                        && idx > 0
-                    {
-                        self.lesson_state.select(Some(idx - 1));
+                    if let Some(idx) = self.lesson_state.selected() {
+                        self.lesson_state.select(Some(idx.saturating_sub(n)));
                     }
                 }
                 Pane::Readme => {
-                    self.readme_scroll = self.readme_scroll.saturating_sub(1);
+                    self.readme_scroll = self.readme_scroll.saturating_sub(n);
+                    // Coming back down to the end tries reading on again
+                    self.reading_stalled_at = None;
                 }
             },
         }
     }
 
-    fn move_down(&mut self) {
+    fn move_down(&mut self, n: usize) {
         match self.view {
             View::CourseList => {
                 let len = self.get_filtered_courses().len();
-                if let Some(idx) = self.course_state.selected()
-                    && idx + 1 < len
-                {
-                    self.course_state.select(Some(idx + 1));
+                if let Some(idx) = self.course_state.selected() {
+                    self.course_state.select(Some(last_within(idx, n, len)));
                 }
             }
             View::CourseContent => match self.active_pane {
                 Pane::Chapters => {
-                    if let Some(idx) = self.chapter_state.selected()
-                        && idx + 1 < self.chapters.len()
-                    {
-                        self.chapter_state.select(Some(idx + 1));
+                    let len = self.filtered_chapters().len();
+                    if let Some(idx) = self.chapter_state.selected() {
+                        self.chapter_state.select(Some(last_within(idx, n, len)));
                     }
                 }
                 Pane::Lessons => {
-                    if let Some(idx) = self.lesson_state.selected()
-                        && idx + 1 < self.lessons.len()
-                    {
-                        self.lesson_state.select(Some(idx + 1));
+                    let len = self.filtered_lessons().len();
+                    if let Some(idx) = self.lesson_state.selected() {
+                        self.lesson_state.select(Some(last_within(idx, n, len)));
                     }
                 }
                 Pane::Readme => {
-                    let line_count = self.readme.lines().count();
-                    // Prevent scrolling past the end (approximate)
-                    // Allow scrolling until the last line is at the top
-                    if self.readme_scroll < line_count.saturating_sub(1) {
-                        self.readme_scroll = self.readme_scroll.saturating_add(1);
+                    // Scrolling past the end reads on into the next lesson
+                    if self.options.continuous_reading
+                        && !self.readme.is_empty()
+                        && self.readme_scroll >= self.readme_max_scroll()
+                    {
+                        self.continue_reading();
                     }
+                    self.readme_scroll = self
+                        .readme_scroll
+                        .saturating_add(n)
+                        .min(self.readme_max_scroll());
                 }
             },
         }
     }
 
+    fn move_to_top(&mut self) {
+        self.move_up(usize::MAX);
+    }
+
+    fn move_to_bottom(&mut self) {
+        self.move_down(usize::MAX);
+    }
+
+    // Rows visible in the active pane, inside its borders
+    fn page_size(&self) -> usize {
+        let height = match self.view {
+            View::CourseList => usize::from(self.areas.courses.height.saturating_sub(2)),
+            View::CourseContent => match self.active_pane {
+                Pane::Chapters => usize::from(self.areas.chapters.height.saturating_sub(2)),
+                Pane::Lessons => usize::from(self.areas.lessons.height.saturating_sub(2)),
+                Pane::Readme => self.readme_height(),
+            },
+        };
+        height.max(1)
+    }
+
     fn select(&mut self) {
         match self.view {
             View::CourseList => {
@@ -84,9 +121,9 @@ impl Navigation for App {
                 if !filtered.is_empty() {
                     self.load_chapters();
                     if !self.chapters.is_empty() {
+                        self.exit_search();
                         self.view = View::CourseContent;
                         self.active_pane = Pane::Chapters;
-                        self.exit_search();
                     }
                 }
             }
@@ -131,11 +168,18 @@ impl Navigation for App {
     fn back_to_courses(&mut self) {
         self.view = View::CourseList;
         self.chapters.clear();
+        self.chapter_filter.clear();
         self.chapters_highlighted.clear();
         self.reset_lesson_content(false);
         self.selected_course_slug = None;
         self.selected_course_title = None;
         self.selected_course_uuid = None;
+        self.indexing = None;
         self.status = format!("Loaded {} courses", self.courses.len());
     }
 }
+
+// Index n steps after idx, stopping at the last of len items
+fn last_within(idx: usize, n: usize, len: usize) -> usize {
+    idx.saturating_add(n).min(len.saturating_sub(1))
+}
@@ -0,0 +1,291 @@
+use crate::{
+    app::{App, Pane, View},
+    fetch,
+    theme::Theme,
+};
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How long the selection has to rest before its preview loads
+const PREVIEW_DELAY: Duration = Duration::from_millis(250);
+// How often to check on a preview being fetched
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewTarget {
+    Lessons { chapter_no: usize },
+    Readme { chapter_no: usize, lesson_no: usize },
+}
+
+// What a background fetch brings back, for the cache
+#[derive(Debug)]
+enum Fetched {
+    Lessons(Vec<String>),
+    Readme {
+        lesson_id: String,
+        raw: String,
+        highlighted: String,
+    },
+}
+
+// A preview waiting for the selection to settle, or being fetched. Dropping
+// it cancels it; a fetch already sent finishes, but its result is ignored.
+#[derive(Debug)]
+pub struct Preview {
+    course_slug: String,
+    target: PreviewTarget,
+    due: Instant,
+    // Brings back the course UUID too, which may have been looked up
+    loading: Option<Receiver<Result<(String, Fetched), anyhow::Error>>>,
+}
+
+impl App {
+    // The chapter or lesson under the cursor, if it isn't the one shown already
+    fn preview_target(&self) -> Option<PreviewTarget> {
+        if !self.options.preview || self.view != View::CourseContent {
+            return None;
+        }
+
+        match self.active_pane {
+            Pane::Chapters => {
+                let chapter_no = self.selected_chapter_idx()? + 1;
+                let is_shown =
+                    self.selected_chapter_no == Some(chapter_no) && !self.lessons.is_empty();
+                (!is_shown).then_some(PreviewTarget::Lessons { chapter_no })
+            }
+            // A whole chapter stays up while moving through its lessons
+            Pane::Lessons if !self.is_chapter_view => {
+                let chapter_no = self.selected_chapter_no?;
+                let lesson_no = self.selected_lesson_idx()? + 1;
+                let is_shown =
+                    self.selected_lesson_no == Some(lesson_no) && !self.readme.is_empty();
+                (!is_shown).then_some(PreviewTarget::Readme {
+                    chapter_no,
+                    lesson_no,
+                })
+            }
+            Pane::Lessons | Pane::Readme => None,
+        }
+    }
+
+    /// Wait for the selection to settle before previewing it, cancelling a
+    /// preview of an earlier selection
+    pub fn schedule_preview(&mut self) {
+        let (Some(target), Some(course_slug)) =
+            (self.preview_target(), self.selected_course_slug.clone())
+        else {
+            self.preview = None;
+            return;
+        };
+
+        if self
+            .preview
+            .as_ref()
+            .is_none_or(|p| p.target != target || p.course_slug != course_slug)
+        {
+            self.preview = Some(Preview {
+                course_slug,
+                target,
+                due: Instant::now() + PREVIEW_DELAY,
+                loading: None,
+            });
+        }
+    }
+
+    /// How long the event loop can wait for input before the preview needs
+    /// attention again
+    #[must_use]
+    pub fn preview_timeout(&self) -> Option<Duration> {
+        let preview = self.preview.as_ref()?;
+        if preview.loading.is_some() {
+            Some(POLL_INTERVAL)
+        } else {
+            Some(preview.due.saturating_duration_since(Instant::now()))
+        }
+    }
+
+    /// Start the preview once it is due, or show it once it has been fetched
+    pub fn poll_preview(&mut self) {
+        let Some(preview) = &self.preview else {
+            return;
+        };
+        let target = preview.target;
+        let course_slug = preview.course_slug.clone();
+
+        let Some(loading) = &preview.loading else {
+            if Instant::now() >= preview.due {
+                self.start_preview(target);
+            }
+            return;
+        };
+
+        let result = match loading.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(anyhow::anyhow!("Preview loading panicked")),
+        };
+        self.preview = None;
+
+        match result {
+            Ok((course_uuid, fetched)) => {
+                self.cache_fetched(target, course_slug, course_uuid, fetched);
+                // The selection may have moved on while this was loading
+                if self.preview_target() == Some(target) {
+                    self.show_preview(target);
+                }
+            }
+            Err(e) => self.status = format!("Error loading preview: {}", e),
+        }
+    }
+
+    fn start_preview(&mut self, target: PreviewTarget) {
+        let Some(course_slug) = self.selected_course_slug.clone() else {
+            self.preview = None;
+            return;
+        };
+        // Opening a course doesn't look up its UUID, so it may still be unknown
+        let course_uuid = self
+            .selected_course_uuid
+            .clone()
+            .or_else(|| self.cache.course_uuid(&course_slug).cloned());
+
+        let lesson_id = match (target, course_uuid.as_deref()) {
+            (_, None) => None,
+            (PreviewTarget::Lessons { chapter_no }, Some(course_uuid)) => {
+                if self.cache.lessons(course_uuid, chapter_no).is_some() {
+                    self.preview = None;
+                    self.show_preview(target);
+                    return;
+                }
+                None
+            }
+            (
+                PreviewTarget::Readme {
+                    chapter_no,
+                    lesson_no,
+                },
+                Some(course_uuid),
+            ) => {
+                let lesson_id = self
+                    .cache
+                    .lesson_id(course_uuid, chapter_no, lesson_no)
+                    .cloned();
+                if lesson_id
+                    .as_ref()
+                    .is_some_and(|id| self.cache.readme(id).is_some())
+                {
+                    self.preview = None;
+                    self.show_preview(target);
+                    return;
+                }
+                lesson_id
+            }
+        };
+
+        let raw = lesson_id
+            .as_ref()
+            .and_then(|id| self.cache.raw_readme(id))
+            .cloned();
+        let theme = self.theme.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result = course_uuid
+                .map_or_else(|| fetch::get_course_id(&course_slug), Ok)
+                .and_then(|course_uuid| {
+                    fetch_preview(target, &course_uuid, lesson_id, raw, &theme)
+                        .map(|fetched| (course_uuid, fetched))
+                });
+            // The preview may have been cancelled in the meantime
+            let _ = sender.send(result);
+        });
+
+        if let Some(preview) = &mut self.preview {
+            preview.loading = Some(receiver);
+        }
+        self.status = String::from("Loading preview...");
+    }
+
+    fn cache_fetched(
+        &mut self,
+        target: PreviewTarget,
+        course_slug: String,
+        course_uuid: String,
+        fetched: Fetched,
+    ) {
+        if self.selected_course_slug.as_ref() == Some(&course_slug) {
+            self.selected_course_uuid = Some(course_uuid.clone());
+        }
+        self.cache.set_course_uuid(course_slug, course_uuid.clone());
+
+        match (target, fetched) {
+            (PreviewTarget::Lessons { chapter_no }, Fetched::Lessons(lessons)) => {
+                self.cache.set_lessons(course_uuid, chapter_no, lessons);
+            }
+            (
+                PreviewTarget::Readme {
+                    chapter_no,
+                    lesson_no,
+                },
+                Fetched::Readme {
+                    lesson_id,
+                    raw,
+                    highlighted,
+                },
+            ) => {
+                self.cache
+                    .set_lesson_id(course_uuid, chapter_no, lesson_no, lesson_id.clone());
+                self.cache.set_raw_readme(lesson_id.clone(), raw);
+                self.cache.set_readme(lesson_id, highlighted);
+            }
+            _ => {}
+        }
+    }
+
+    // Load from the cache into the neighbouring pane, leaving the focus alone
+    fn show_preview(&mut self, target: PreviewTarget) {
+        match target {
+            PreviewTarget::Lessons { .. } => self.load_lessons(),
+            PreviewTarget::Readme { .. } => {
+                self.load_readme();
+                self.readme_scroll = 0;
+            }
+        }
+    }
+}
+
+// Runs on a background thread, so only takes what it needs from the app
+fn fetch_preview(
+    target: PreviewTarget,
+    course_uuid: &str,
+    lesson_id: Option<String>,
+    raw: Option<String>,
+    theme: &Theme,
+) -> Result<Fetched, anyhow::Error> {
+    match target {
+        PreviewTarget::Lessons { chapter_no } => {
+            fetch::get_lessons_by_course_id(course_uuid, chapter_no).map(Fetched::Lessons)
+        }
+        PreviewTarget::Readme {
+            chapter_no,
+            lesson_no,
+        } => {
+            let lesson_id = match lesson_id {
+                Some(id) => id,
+                None => fetch::get_lesson_id_by_course_id(course_uuid, chapter_no, lesson_no)?,
+            };
+            let raw = match raw {
+                Some(raw) => raw,
+                None => fetch::get_readme_by_id(&lesson_id)?,
+            };
+            let highlighted = App::highlight_markdown(&raw, theme);
+            Ok(Fetched::Readme {
+                lesson_id,
+                raw,
+                highlighted,
+            })
+        }
+    }
+}
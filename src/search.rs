@@ -0,0 +1,242 @@
+use crate::cache::Cache;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+// A case-insensitive match within one line of a readme, in chars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadmeMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[must_use]
+pub fn find_matches(lines: &[String], query: &str) -> Vec<ReadmeMatch> {
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+
+    for (line_no, line) in lines.iter().enumerate() {
+        let haystack: Vec<char> = line.chars().map(fold).collect();
+        let mut start = 0;
+
+        while start + needle.len() <= haystack.len() {
+            if haystack[start..].starts_with(&needle) {
+                matches.push(ReadmeMatch {
+                    line: line_no,
+                    start,
+                    end: start + needle.len(),
+                });
+                start += needle.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    matches
+}
+
+// Lowercase one char to one char, so that offsets stay aligned
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedLesson {
+    pub chapter_no: usize,
+    pub lesson_no: usize,
+    pub title: String,
+    pub lines: Vec<String>, // Plain text of the rendered readme
+}
+
+#[derive(Debug, Clone)]
+pub struct CourseHit {
+    pub chapter_no: usize,
+    pub lesson_no: usize,
+    pub title: String,
+    pub line: usize,
+    pub start: usize,
+    pub context: String,
+}
+
+// Inverted index over the readmes of one course
+#[derive(Debug, Default)]
+pub struct CourseIndex {
+    lessons: Vec<IndexedLesson>,
+    postings: BTreeMap<String, BTreeSet<usize>>, // word -> lesson indices
+}
+
+const MAX_HITS: usize = 500;
+const CONTEXT_CHARS: usize = 60;
+
+impl CourseIndex {
+    #[must_use]
+    pub fn build(lessons: Vec<IndexedLesson>) -> Self {
+        let mut postings: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+
+        for (i, lesson) in lessons.iter().enumerate() {
+            for word in lesson.lines.iter().flat_map(|line| tokenize(line)) {
+                postings.entry(word).or_default().insert(i);
+            }
+        }
+
+        Self { lessons, postings }
+    }
+
+    #[must_use]
+    pub fn search(&self, query: &str) -> Vec<CourseHit> {
+        if query.trim().is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits = Vec::new();
+
+        for i in self.candidates(query) {
+            let lesson = &self.lessons[i];
+            for m in find_matches(&lesson.lines, query) {
+                hits.push(CourseHit {
+                    chapter_no: lesson.chapter_no,
+                    lesson_no: lesson.lesson_no,
+                    title: lesson.title.clone(),
+                    line: m.line,
+                    start: m.start,
+                    context: context(&lesson.lines[m.line], m.start, m.end),
+                });
+                if hits.len() >= MAX_HITS {
+                    return hits;
+                }
+            }
+        }
+
+        hits
+    }
+
+    // Lessons containing every word of the query; the first and last words
+    // may be partial, since the query can start or end mid-word
+    fn candidates(&self, query: &str) -> BTreeSet<usize> {
+        let words = tokenize(query);
+        if words.is_empty() {
+            return (0..self.lessons.len()).collect();
+        }
+
+        let mut result: Option<BTreeSet<usize>> = None;
+
+        for (i, word) in words.iter().enumerate() {
+            let is_partial = i == 0 || i == words.len() - 1;
+            let lessons: BTreeSet<usize> = if is_partial {
+                self.postings
+                    .iter()
+                    .filter(|(w, _)| w.contains(word.as_str()))
+                    .flat_map(|(_, ids)| ids.iter().copied())
+                    .collect()
+            } else {
+                self.postings.get(word).cloned().unwrap_or_default()
+            };
+
+            result = Some(match result {
+                None => lessons,
+                Some(prev) => prev.intersection(&lessons).copied().collect(),
+            });
+        }
+
+        result.unwrap_or_default()
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Up to CONTEXT_CHARS of the line around a match, with ellipses
+fn context(line: &str, start: usize, end: usize) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let match_len = end - start;
+    let padding = CONTEXT_CHARS.saturating_sub(match_len) / 2;
+
+    let from = start.saturating_sub(padding);
+    let to = (end + padding).min(chars.len());
+
+    let mut snippet: String = chars[from..to].iter().collect::<String>().trim().to_owned();
+    if from > 0 {
+        snippet.insert(0, '…');
+    }
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+// Query for searching the persisted cache from the command line
+pub enum Pattern {
+    Plain(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn new(query: &str, is_regex: bool) -> Result<Self, anyhow::Error> {
+        if is_regex {
+            Ok(Self::Regex(RegexBuilder::new(query).build()?))
+        } else {
+            Ok(Self::Plain(query.to_lowercase()))
+        }
+    }
+
+    #[must_use]
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Plain(query) => text.to_lowercase().contains(query),
+            Self::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheHit {
+    pub course: String,
+    pub chapter: usize,
+    pub lesson: usize,
+    pub title: String,
+    pub line: Option<usize>, // None for a match in the title
+    pub text: String,
+}
+
+/// Search lesson titles and readmes in the cache, optionally within one course
+#[must_use]
+pub fn search_cache(cache: &Cache, pattern: &Pattern, course: Option<&str>) -> Vec<CacheHit> {
+    let mut hits = Vec::new();
+
+    for lesson in cache.cached_lessons() {
+        if course.is_some_and(|slug| slug != lesson.course_slug) {
+            continue;
+        }
+
+        let hit = |line, text: &str| CacheHit {
+            course: lesson.course_slug.to_owned(),
+            chapter: lesson.chapter_no,
+            lesson: lesson.lesson_no,
+            title: lesson.title.to_owned(),
+            line,
+            text: text.trim().to_owned(),
+        };
+
+        if pattern.is_match(lesson.title) {
+            hits.push(hit(None, lesson.title));
+        }
+
+        for (i, line) in lesson.readme.unwrap_or_default().lines().enumerate() {
+            if pattern.is_match(line) {
+                hits.push(hit(Some(i + 1), line));
+            }
+        }
+    }
+
+    hits
+}
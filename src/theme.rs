@@ -0,0 +1,190 @@
+use crate::config::config_dir;
+use anyhow::{Context, anyhow};
+use bat::PrettyPrinter;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    widgets::BorderType,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+pub const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "mono"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Theme {
+    // UI chrome
+    pub border: Style,
+    pub border_active: Style,
+    pub border_type: BorderType,
+    pub border_type_active: BorderType,
+    pub title: Style,
+    pub title_active: Style,
+    pub selection: Style,
+    pub selected: Style, // Currently loaded chapter/lesson
+    pub status: Style,
+    pub hint: Style,
+
+    // Readme elements
+    pub placeholder: Style,
+    pub breadcrumb: Style,
+    pub section: Style,
+    pub search_match: Style,
+    pub search_current: Style,
+    pub fuzzy_match: Style, // Matched chars in the courses list
+
+    // bat/syntect theme for syntax highlighting
+    pub syntax: String,
+
+    // Whether the readme and lists keep their syntax colors
+    pub color: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    #[must_use]
+    pub fn dark() -> Self {
+        Self {
+            border: Style::default().fg(Color::DarkGray),
+            border_active: Style::default().fg(Color::Cyan),
+            border_type: BorderType::Plain,
+            border_type_active: BorderType::Plain,
+            title: Style::default().fg(Color::White),
+            title_active: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            selection: Style::default().bg(Color::DarkGray).fg(Color::White),
+            selected: Style::default().fg(Color::Green),
+            status: Style::default().fg(Color::Cyan),
+            hint: Style::default().fg(Color::DarkGray),
+            placeholder: Style::default().fg(Color::DarkGray),
+            breadcrumb: Style::default().fg(Color::Cyan),
+            section: Style::default().fg(Color::Green),
+            search_match: Style::default().bg(Color::Yellow).fg(Color::Black),
+            search_current: Style::default().bg(Color::LightRed).fg(Color::Black),
+            fuzzy_match: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            syntax: String::from("Monokai Extended"),
+            color: true,
+        }
+    }
+
+    #[must_use]
+    pub fn light() -> Self {
+        Self {
+            border: Style::default().fg(Color::Gray),
+            border_active: Style::default().fg(Color::Blue),
+            border_type: BorderType::Plain,
+            border_type_active: BorderType::Plain,
+            title: Style::default().fg(Color::Black),
+            title_active: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            selection: Style::default().bg(Color::Gray).fg(Color::Black),
+            selected: Style::default().fg(Color::Magenta),
+            status: Style::default().fg(Color::Blue),
+            hint: Style::default().fg(Color::DarkGray),
+            placeholder: Style::default().fg(Color::DarkGray),
+            breadcrumb: Style::default().fg(Color::Blue),
+            section: Style::default().fg(Color::Magenta),
+            search_match: Style::default().bg(Color::LightYellow).fg(Color::Black),
+            search_current: Style::default().bg(Color::LightRed).fg(Color::Black),
+            fuzzy_match: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            syntax: String::from("Monokai Extended Light"),
+            color: true,
+        }
+    }
+
+    // High-contrast theme without colors, for NO_COLOR and --no-color
+    #[must_use]
+    pub fn mono() -> Self {
+        Self {
+            border: Style::default(),
+            border_active: Style::default().add_modifier(Modifier::BOLD),
+            border_type: BorderType::Plain,
+            border_type_active: BorderType::Thick,
+            title: Style::default(),
+            title_active: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            selected: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            status: Style::default().add_modifier(Modifier::BOLD),
+            hint: Style::default(),
+            placeholder: Style::default().add_modifier(Modifier::ITALIC),
+            breadcrumb: Style::default().add_modifier(Modifier::BOLD),
+            section: Style::default().add_modifier(Modifier::UNDERLINED),
+            search_match: Style::default().add_modifier(Modifier::REVERSED),
+            search_current: Style::default()
+                .add_modifier(Modifier::REVERSED | Modifier::BOLD | Modifier::UNDERLINED),
+            fuzzy_match: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            syntax: String::from("Monokai Extended"),
+            color: false,
+        }
+    }
+
+    #[must_use]
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "mono" => Some(Self::mono()),
+            _ => None,
+        }
+    }
+
+    /// Load a built-in theme by name, or a custom theme from a file path or
+    /// from `themes/<name>.toml` in the config directory
+    pub fn load(name: &str) -> Result<Self, anyhow::Error> {
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+
+        let path = Path::new(name);
+        if path.is_file() {
+            return Self::from_file(path);
+        }
+
+        let path = config_dir()
+            .map(|dir| dir.join("themes").join(format!("{}.toml", name)))
+            .filter(|p| p.is_file())
+            .context(format!(
+                "Unknown theme '{}' (built-in themes: {})",
+                name,
+                BUILTIN_THEMES.join(", ")
+            ))?;
+        Self::from_file(&path)
+    }
+
+    // Custom themes override any subset of a built-in `base` theme (dark by default)
+    fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path)
+            .context(format!("Failed to read theme file {}", path.display()))?;
+        let mut overrides: toml::Table =
+            toml::from_str(&contents).context(format!("Invalid theme file {}", path.display()))?;
+
+        let base = match overrides.remove("base") {
+            None => Self::dark(),
+            Some(toml::Value::String(name)) => {
+                Self::builtin(&name).ok_or_else(|| anyhow!("Unknown base theme '{}'", name))?
+            }
+            Some(_) => return Err(anyhow!("Theme `base` must be a string")),
+        };
+
+        let mut table = toml::Table::try_from(base)?;
+        table.extend(overrides);
+        let theme: Self = table
+            .try_into()
+            .context(format!("Invalid theme file {}", path.display()))?;
+
+        // bat would otherwise print a warning over the TUI and fall back
+        if !PrettyPrinter::new().themes().any(|t| t == theme.syntax) {
+            return Err(anyhow!("Unknown syntax theme '{}'", theme.syntax));
+        }
+
+        Ok(theme)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::plain_lines;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub level: usize,
+    pub title: String,
+    pub line: usize, // Line index in the readme
+}
+
+#[must_use]
+pub fn build(readme: &str) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut in_code_block = false;
+
+    for (i, line) in plain_lines(readme).iter().enumerate() {
+        let trimmed = line.trim_start();
+
+        // Skip comments in fenced code blocks (e.g., `# foo` in Python)
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        if let Some((level, title)) = parse_heading(trimmed) {
+            entries.push(TocEntry {
+                level,
+                title,
+                line: i,
+            });
+        }
+    }
+
+    entries
+}
+
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    if title.is_empty() {
+        return None;
+    }
+
+    Some((level, title.to_owned()))
+}
+
+/// Index of the TOC entry containing the given line, if any
+#[must_use]
+pub fn section_at(entries: &[TocEntry], line: usize) -> Option<usize> {
+    entries.iter().rposition(|e| e.line <= line)
+}
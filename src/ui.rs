@@ -1,12 +1,23 @@
-use crate::app::{App, Pane, View};
+use crate::{
+    app::{App, MIN_PANE_WIDTH, Overlay, Pane, View},
+    config::PaneSize,
+    input,
+    keymap::{Action, Mode},
+    theme::Theme,
+    wrap::{Highlight, wrap_lines},
+};
 use ansi_to_tui::IntoText;
 use ratatui::{
     Frame,
-    layout::{Constraint, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::{Line, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
 };
+use unicode_width::UnicodeWidthStr;
 
 pub fn render(app: &mut App, frame: &mut Frame) {
     let [main_area, status_area] =
@@ -18,6 +29,13 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     }
 
     render_status(app, frame, status_area);
+
+    match app.overlay {
+        Overlay::CourseSearch => render_course_search(app, frame, main_area),
+        Overlay::LessonFinder => render_lesson_finder(app, frame, main_area),
+        Overlay::Help => render_help(app, frame, main_area),
+        Overlay::None | Overlay::Search | Overlay::Toc => {}
+    }
 }
 
 fn render_course_list_view(app: &mut App, frame: &mut Frame, area: Rect) {
@@ -28,82 +46,152 @@ fn render_course_list_view(app: &mut App, frame: &mut Frame, area: Rect) {
         let filtered_courses = app.get_filtered_courses();
         filtered_courses
             .iter()
-            .map(|(_, title)| ListItem::new(title.clone()))
+            .map(|course| {
+                let mut spans =
+                    highlight_chars(course.title, &course.title_matches, app.theme.fuzzy_match);
+                // Show the slug when that's what matched
+                if !course.slug_matches.is_empty() {
+                    spans.push(Span::styled(" · ", app.theme.hint));
+                    spans.extend(
+                        highlight_chars(course.slug, &course.slug_matches, app.theme.fuzzy_match)
+                            .into_iter()
+                            .map(|span| span.patch_style(app.theme.hint)),
+                    );
+                }
+                ListItem::new(Line::from(spans))
+            })
             .collect()
     };
 
-    let block = make_block("Courses", !app.is_search_mode);
+    let is_searching = app.overlay == Overlay::Search;
+    let block = make_block("Courses", !is_searching, &app.theme);
     let list = List::new(items)
         .block(block)
-        .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_style(app.theme.selection)
         .highlight_symbol("> ");
 
     frame.render_stateful_widget(list, list_area, &mut app.course_state);
+    app.areas.courses = list_area;
 
-    let search_block = make_block("Search", app.is_search_mode);
+    let search_block = make_block("Search", is_searching, &app.theme);
     let search_text = Paragraph::new(app.search_query.as_str()).block(search_block);
     frame.render_widget(search_text, search_area);
 
-    if app.is_search_mode {
-        // Position cursor at end of text
-        // (x + 1 for border, + length of query)
+    if is_searching {
+        // (x + 1 for border, + width of query before the cursor)
         // (y + 1 for border)
         frame.set_cursor_position((
-            search_area.x + 1 + u16::try_from(app.search_query.chars().count()).unwrap(),
+            search_area.x + 1 + input::cursor_x(&app.search_query, app.search_cursor),
             search_area.y + 1,
         ));
     }
 }
 
+// Readme width in zen mode: 80 columns of text, plus borders and some margin
+const ZEN_WIDTH: u16 = 86;
+
+// Narrower than this, the panes are shown one at a time
+const NARROW_WIDTH: u16 = 100;
+
 fn render_course_content_view(app: &mut App, frame: &mut Frame, area: Rect) {
+    if app.is_zen_mode && app.active_pane == Pane::Readme {
+        let readme_area = centered_rect(area, ZEN_WIDTH, area.height);
+        app.areas.chapters = Rect::default();
+        app.areas.lessons = Rect::default();
+        app.areas.readme = readme_area;
+        render_readme(app, frame, readme_area);
+        return;
+    }
+
+    if area.width < NARROW_WIDTH {
+        render_narrow_view(app, frame, area);
+        return;
+    }
+
     let [title_area, content_area] =
         Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
 
     render_course_title(app, frame, title_area);
 
-    // Calculate dynamic widths for chapters and lessons
-    // Add 2 for borders, 2 for "> ", 3 or 4 for no., 2 for padding
-
-    let ch_padding = if app.chapters.len() >= 10 { 10 } else { 9 };
-    let max_chapter_len = app
-        .chapters
-        .iter()
-        .map(|c| c.chars().count())
-        .max()
-        .unwrap_or(0)
-        + ch_padding;
-
-    let lesson_padding = if app.lessons.len() >= 10 { 10 } else { 9 };
-    let max_lesson_len = app
-        .lessons
-        .iter()
-        .map(|l| l.chars().count())
-        .max()
-        .unwrap_or(0)
-        + lesson_padding;
-
     let max_allowed = (area.width / 5).max(20); // min 20 chars, max 20%
+    let max_resized = (area.width / 3).max(MIN_PANE_WIDTH); // Leave the readme room
+    let chapters_width =
+        side_pane_width(app.layout.chapters, &app.chapters, max_allowed, max_resized);
+    let lessons_width = side_pane_width(app.layout.lessons, &app.lessons, max_allowed, max_resized);
 
     let panes = Layout::horizontal([
-        Constraint::Length(
-            (u16::try_from(max_chapter_len).unwrap_or(20))
-                .max(20)
-                .min(max_allowed),
-        ),
-        Constraint::Length(
-            (u16::try_from(max_lesson_len).unwrap_or(20))
-                .max(20)
-                .min(max_allowed),
-        ),
+        Constraint::Length(chapters_width),
+        Constraint::Length(lessons_width),
         Constraint::Min(0),
     ])
     .split(content_area);
 
-    render_chapters(app, frame, panes[0]);
-    render_lessons(app, frame, panes[1]);
+    app.areas.chapters = panes[0];
+    app.areas.lessons = panes[1];
+    app.areas.readme = panes[2];
+
+    render_chapters(app, frame, panes[0], app.layout.chapters.collapsed);
+    render_lessons(app, frame, panes[1], app.layout.lessons.collapsed);
     render_readme(app, frame, panes[2]);
 }
 
+// Width of a side pane: a strip for its numbers, the width it was resized to, or
+// fitted to its titles
+fn side_pane_width(size: PaneSize, titles: &[String], max_allowed: u16, max_resized: u16) -> u16 {
+    // Add 2 for borders, 2 for "> ", 3 or 4 for no., 2 for padding
+    let padding = if titles.len() >= 10 { 10 } else { 9 };
+
+    if size.collapsed {
+        let digits = titles.len().max(1).ilog10() + 1;
+        return u16::try_from(digits).unwrap_or(u16::MAX) + 5;
+    }
+
+    // Only clamped for this terminal; the saved width stays as it was
+    if let Some(width) = size.width {
+        return width.clamp(MIN_PANE_WIDTH, max_resized);
+    }
+
+    let max_title_len = titles.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+    u16::try_from(max_title_len + padding)
+        .unwrap_or(20)
+        .max(20)
+        .min(max_allowed)
+}
+
+// Only the focused pane, under breadcrumbs in place of the course title. The
+// readme has its own breadcrumb header, so it gets the whole height.
+fn render_narrow_view(app: &mut App, frame: &mut Frame, area: Rect) {
+    let pane_area = if app.active_pane == Pane::Readme {
+        area
+    } else {
+        let [title_area, pane_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+        let paragraph =
+            Paragraph::new(breadcrumb_line(app)).block(Block::default().borders(Borders::ALL));
+        frame.render_widget(paragraph, title_area);
+        pane_area
+    };
+
+    app.areas.chapters = Rect::default();
+    app.areas.lessons = Rect::default();
+    app.areas.readme = Rect::default();
+
+    match app.active_pane {
+        Pane::Chapters => {
+            app.areas.chapters = pane_area;
+            render_chapters(app, frame, pane_area, false);
+        }
+        Pane::Lessons => {
+            app.areas.lessons = pane_area;
+            render_lessons(app, frame, pane_area, false);
+        }
+        Pane::Readme => {
+            app.areas.readme = pane_area;
+            render_readme(app, frame, pane_area);
+        }
+    }
+}
+
 fn render_course_title(app: &App, frame: &mut Frame, area: Rect) {
     let title = app
         .selected_course_title
@@ -111,104 +199,532 @@ fn render_course_title(app: &App, frame: &mut Frame, area: Rect) {
         .unwrap_or("Unknown Course");
 
     let block = Block::default().borders(Borders::ALL);
-    let paragraph = Paragraph::new(title).block(block).style(
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD),
-    );
+    let paragraph = Paragraph::new(title)
+        .block(block)
+        .style(app.theme.title_active);
 
     frame.render_widget(paragraph, area);
 }
 
-fn render_chapters(app: &mut App, frame: &mut Frame, area: Rect) {
-    let fallback = numbered_items(&app.chapters, None);
-    let items = items_from_highlighted(&app.chapters_highlighted, fallback, None, Color::Green);
+// A collapsed pane lists only the numbers
+fn render_chapters(app: &mut App, frame: &mut Frame, area: Rect, collapsed: bool) {
+    let items = if collapsed {
+        number_items(app.chapters.len(), None, app.theme.selected)
+    } else {
+        let fallback = numbered_items(&app.chapters, None, app.theme.selected);
+        items_from_highlighted(
+            &app.chapters_highlighted,
+            fallback,
+            None,
+            app.theme.selected,
+        )
+    };
+    let items = keep_items(items, &app.filtered_chapters());
 
     let is_active = app.active_pane == Pane::Chapters;
-    let block = make_block("Chapters", is_active);
+    let title = filtered_title("Chapters", &app.chapter_filter);
+    let block = make_block(&title, is_active, &app.theme);
     let list = List::new(items)
         .block(block)
-        .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_style(app.theme.selection)
         .highlight_symbol("> ");
 
     frame.render_stateful_widget(list, area, &mut app.chapter_state);
 }
 
-fn render_lessons(app: &mut App, frame: &mut Frame, area: Rect) {
+fn render_lessons(app: &mut App, frame: &mut Frame, area: Rect, collapsed: bool) {
     let is_active = app.active_pane == Pane::Lessons;
-    let block = make_block("Lessons", is_active);
+    let title = filtered_title("Lessons", &app.lesson_filter);
+    let block = make_block(&title, is_active, &app.theme);
 
     if app.lessons.is_empty() {
-        let content = Text::from("Select a chapter").style(Style::default().fg(Color::DarkGray));
+        let content = Text::from("Select a chapter").style(app.theme.placeholder);
         let paragraph = Paragraph::new(content)
             .block(block)
             .wrap(Wrap { trim: false });
         frame.render_widget(paragraph, area);
     } else {
-        let fallback = numbered_items(&app.lessons, app.selected_lesson_no);
-        let items = items_from_highlighted(
-            &app.lessons_highlighted,
-            fallback,
-            app.selected_lesson_no,
-            Color::Green,
-        );
+        let items = if collapsed {
+            number_items(
+                app.lessons.len(),
+                app.selected_lesson_no,
+                app.theme.selected,
+            )
+        } else {
+            let fallback = numbered_items(&app.lessons, app.selected_lesson_no, app.theme.selected);
+            items_from_highlighted(
+                &app.lessons_highlighted,
+                fallback,
+                app.selected_lesson_no,
+                app.theme.selected,
+            )
+        };
+        let items = keep_items(items, &app.filtered_lessons());
 
         let list = List::new(items)
             .block(block)
-            .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+            .highlight_style(app.theme.selection)
             .highlight_symbol("> ");
 
         frame.render_stateful_widget(list, area, &mut app.lesson_state);
     }
 }
 
-fn render_readme(app: &App, frame: &mut Frame, area: Rect) {
+fn render_readme(app: &mut App, frame: &mut Frame, area: Rect) {
     let is_active = app.active_pane == Pane::Readme;
-    let block = make_block("Readme", is_active);
+    let title = match app.selected_chapter_no {
+        Some(ch_no) if app.is_chapter_view => format!("Chapter {}", ch_no),
+        _ => String::from("Readme"),
+    };
+    let block = make_block(&title, is_active, &app.theme);
+    let inner = block.inner(area);
+
+    if app.readme.is_empty() {
+        frame.render_widget(block, area);
+        let content = Text::from("Select a lesson").style(app.theme.placeholder);
+        let paragraph = Paragraph::new(content).wrap(Wrap { trim: false });
+        frame.render_widget(paragraph, inner);
+        app.readme_rows.clear();
+        return;
+    }
+
+    // Sticky breadcrumb header above the scrolling content
+    let [header_area, body_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    frame.render_widget(Paragraph::new(breadcrumb_line(app)), header_area);
+
+    let content = app
+        .readme
+        .as_bytes()
+        .into_text()
+        .unwrap_or_else(|_| Text::from(app.readme.as_str()));
+
+    let highlights: Vec<Highlight> = app
+        .readme_matches
+        .iter()
+        .enumerate()
+        .map(|(i, m)| Highlight {
+            line: m.line,
+            start: m.start,
+            end: m.end,
+            style: if Some(i) == app.current_match {
+                app.theme.search_current
+            } else {
+                app.theme.search_match
+            },
+        })
+        .collect();
+
+    // Wrap here rather than in the paragraph, so that we know which row
+    // each line (and search match) lands on
+    let wrapped = wrap_lines(&content.lines, usize::from(body_area.width), &highlights);
+    app.readme_rows = wrapped.rows;
+    if let Some((line, ch)) = app.readme_jump.take() {
+        app.readme_scroll = app.row_of(line, ch);
+    }
 
-    let content = if app.readme.is_empty() {
-        Text::from("Select a lesson").style(Style::default().fg(Color::DarkGray))
+    // The pane may have grown (or the text got shorter) since we scrolled
+    let max_scroll = app.readme_max_scroll();
+    app.readme_scroll = app.readme_scroll.min(max_scroll);
+    app.sync_reading_position();
+
+    let block = if let Some(percent) = (app.readme_scroll * 100).checked_div(max_scroll) {
+        block.title_bottom(Line::from(format!(" {}% ", percent)).right_aligned())
     } else {
-        app.readme
-            .as_bytes()
-            .into_text()
-            .unwrap_or_else(|_| Text::from(app.readme.as_str()))
+        block
     };
+    frame.render_widget(block, area);
+
+    // Slice out the visible rows, as Paragraph::scroll can't go past u16::MAX rows
+    let visible: Vec<Line> = wrapped
+        .lines
+        .into_iter()
+        .skip(app.readme_scroll)
+        .take(usize::from(body_area.height))
+        .collect();
+    let paragraph = Paragraph::new(visible);
+
+    frame.render_widget(paragraph, body_area);
+
+    if max_scroll > 0 {
+        let mut scrollbar_state = ScrollbarState::new(max_scroll + 1)
+            .viewport_content_length(app.readme_height())
+            .position(app.readme_scroll);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .style(if is_active {
+                app.theme.border_active
+            } else {
+                app.theme.border
+            });
+        frame.render_stateful_widget(
+            scrollbar,
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar_state,
+        );
+    }
 
-    let paragraph = Paragraph::new(content)
-        .block(block)
-        .wrap(Wrap { trim: false })
-        .scroll((u16::try_from(app.readme_scroll).unwrap(), 0));
+    if app.overlay == Overlay::Toc {
+        render_toc(app, frame, area);
+    }
+}
 
-    frame.render_widget(paragraph, area);
+fn breadcrumb_line(app: &App) -> Line<'_> {
+    let separator = app.theme.hint;
+    let mut spans = Vec::new();
+
+    for (i, crumb) in app.breadcrumb().into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" › ", separator));
+        }
+        spans.push(Span::styled(crumb, app.theme.breadcrumb));
+    }
+
+    if let Some(section) = app.current_section() {
+        spans.push(Span::styled(" § ", separator));
+        spans.push(Span::styled(section.title.as_str(), app.theme.section));
+    }
+
+    Line::from(spans).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn render_toc(app: &mut App, frame: &mut Frame, area: Rect) {
+    let items: Vec<ListItem> = app
+        .toc
+        .iter()
+        .map(|entry| {
+            let indent = "  ".repeat(entry.level.saturating_sub(1));
+            ListItem::new(format!("{}{}", indent, entry.title))
+        })
+        .collect();
+
+    let max_title_len = app
+        .toc
+        .iter()
+        .map(|e| e.title.chars().count() + 2 * e.level.saturating_sub(1))
+        .max()
+        .unwrap_or(0);
+
+    // Add 2 for borders, 2 for "> ", 2 for padding
+    let width = u16::try_from(max_title_len + 6)
+        .unwrap_or(u16::MAX)
+        .max(24)
+        .min(area.width);
+    let height = u16::try_from(app.toc.len() + 2)
+        .unwrap_or(u16::MAX)
+        .min(area.height);
+    let popup = centered_rect(area, width, height);
+
+    let list = List::new(items)
+        .block(make_block("Contents", true, &app.theme))
+        .highlight_style(app.theme.selection)
+        .highlight_symbol("> ");
+
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut app.toc_state);
+}
+
+fn render_course_search(app: &mut App, frame: &mut Frame, area: Rect) {
+    let list_area = render_picker(
+        frame,
+        area,
+        "Search course",
+        &app.course_search_query,
+        &app.theme,
+    );
+
+    let items: Vec<ListItem> = app
+        .course_hits
+        .iter()
+        .map(|hit| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("Ch {} · Lesson {}", hit.chapter_no, hit.lesson_no),
+                    app.theme.section,
+                ),
+                Span::styled(" — ", app.theme.hint),
+                Span::raw(hit.context.as_str()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(app.theme.selection)
+        .highlight_symbol("> ");
+
+    frame.render_stateful_widget(list, list_area, &mut app.course_hit_state);
+}
+
+fn render_lesson_finder(app: &mut App, frame: &mut Frame, area: Rect) {
+    let list_area = render_picker(
+        frame,
+        area,
+        "Go to lesson",
+        &app.lesson_finder_query,
+        &app.theme,
+    );
+
+    let items: Vec<ListItem> = app
+        .get_filtered_lessons()
+        .iter()
+        .map(|lesson| {
+            let spans = highlight_chars(&lesson.label, &lesson.matches, app.theme.fuzzy_match);
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(app.theme.selection)
+        .highlight_symbol("> ");
+
+    frame.render_stateful_widget(list, list_area, &mut app.lesson_finder_state);
+}
+
+// Draw a popup with a query line, returning the area left for results
+fn render_picker(frame: &mut Frame, area: Rect, title: &str, query: &str, theme: &Theme) -> Rect {
+    let popup = centered_rect(area, area.width * 4 / 5, area.height * 4 / 5);
+    let block = make_block(title, true, theme);
+    let inner = block.inner(popup);
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(block, popup);
+
+    let [input_area, list_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    let prompt = format!("> {}", query);
+    frame.set_cursor_position((
+        input_area.x + input::cursor_x(&prompt, usize::MAX),
+        input_area.y,
+    ));
+    frame.render_widget(
+        Paragraph::new(prompt).block(Block::default().borders(Borders::BOTTOM)),
+        input_area,
+    );
+
+    list_area
+}
+
+const HELP_CATEGORIES: [&str; 6] = [
+    "General",
+    "Moving",
+    "Opening",
+    "Searching",
+    "Layout",
+    "While typing a search",
+];
+
+fn render_help(app: &mut App, frame: &mut Frame, area: Rect) {
+    let context = app.pane_modes();
+    let mut bindings = app.keymap.bindings(context);
+    // Typing keys also apply once a search is started from here
+    if !context.contains(&Mode::Search) && bindings.iter().any(|(a, _)| *a == Action::Search) {
+        bindings.extend(app.keymap.bindings(&[Mode::Search]));
+    }
+
+    let entries: Vec<(String, &str, &str)> = bindings
+        .iter()
+        .map(|(action, keys)| {
+            let keys: Vec<String> = keys.iter().map(ToString::to_string).collect();
+            (keys.join(", "), action.description(), action.category())
+        })
+        .collect();
+    let key_width = entries
+        .iter()
+        .map(|(keys, _, _)| keys.width())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for category in HELP_CATEGORIES {
+        let in_category: Vec<_> = entries.iter().filter(|(_, _, c)| *c == category).collect();
+        if in_category.is_empty() {
+            continue;
+        }
+
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::styled(category, app.theme.section));
+        for (keys, description, _) in in_category {
+            let padding = " ".repeat(key_width - keys.width());
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(format!("{}{}", keys, padding), app.theme.title_active),
+                Span::raw("  "),
+                Span::raw(*description),
+            ]));
+        }
+        if category == "Moving" && context.contains(&Mode::Normal) {
+            lines.push(Line::styled(
+                "  Type a count first to repeat a motion (e.g., 5j)",
+                app.theme.hint,
+            ));
+        }
+    }
+
+    let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+    // Add 2 for borders, 2 for padding
+    let width = u16::try_from(content_width + 4)
+        .unwrap_or(u16::MAX)
+        .max(24)
+        .min(area.width);
+    let height = u16::try_from(lines.len() + 2)
+        .unwrap_or(u16::MAX)
+        .min(area.height);
+    let popup = centered_rect(area, width, height);
+    app.areas.help = popup;
+
+    let max_scroll = (lines.len() + 2).saturating_sub(usize::from(height));
+    app.help_scroll = app.help_scroll.min(max_scroll);
+
+    let block = make_block("Help", true, &app.theme);
+    let block = if max_scroll > 0 {
+        let hint = app.keymap.help(
+            &[Mode::Help],
+            &[(&[Action::MoveDown, Action::MoveUp], "scroll")],
+        );
+        block.title_bottom(Line::from(hint).right_aligned())
+    } else {
+        block
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(block.padding(Padding::horizontal(1)))
+        .scroll((u16::try_from(app.help_scroll).unwrap_or(u16::MAX), 0));
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(paragraph, popup);
 }
 
 fn render_status(app: &App, frame: &mut Frame, area: Rect) {
-    let help = if app.is_search_mode {
-        " Esc: cancel | Enter: finish | Typing... "
+    let help = app.keymap.help(app.key_modes(), help_entries(app));
+    let in_readme = app.in_readme();
+
+    // Outside the course list, the search query is typed into the status bar
+    let mut spans = if app.view == View::CourseContent && app.overlay == Overlay::Search {
+        let prompt = format!("/{}", app.query());
+        // (x + 1 for border, + 1 for the slash, + width before the cursor)
+        frame.set_cursor_position((
+            area.x + 2 + input::cursor_x(app.query(), app.search_cursor),
+            area.y + 1,
+        ));
+        vec![ratatui::text::Span::styled(prompt, app.theme.status)]
     } else {
-        match app.view {
-            View::CourseList => " q: quit | /: search | j/k: down/up | l: select ",
-            View::CourseContent => match app.active_pane {
-                Pane::Chapters | Pane::Lessons => {
-                    " q: quit | Esc: courses | h/l: back/forward | j/k: down/up "
-                }
-                Pane::Readme => " q: quit | Esc: courses | h: back | j/k: scroll ",
-            },
-        }
+        vec![ratatui::text::Span::styled(
+            app.status.as_str(),
+            app.theme.status,
+        )]
     };
-    let status_line = Line::from(vec![
-        ratatui::text::Span::styled(&app.status, Style::default().fg(Color::Cyan)),
-        ratatui::text::Span::raw(" | "),
-        ratatui::text::Span::styled(help, Style::default().fg(Color::DarkGray)),
-    ]);
+
+    if in_readme && !app.readme_matches.is_empty() {
+        let current = app.current_match.map_or(0, |idx| idx + 1);
+        spans.push(ratatui::text::Span::raw(" | "));
+        spans.push(ratatui::text::Span::styled(
+            format!("match {}/{}", current, app.readme_matches.len()),
+            app.theme.search_current,
+        ));
+    }
+
+    if in_readme && let Some(section) = app.current_section() {
+        spans.push(ratatui::text::Span::raw(" | "));
+        spans.push(ratatui::text::Span::styled(
+            format!("§ {}", section.title),
+            app.theme.section,
+        ));
+    }
+
+    if let Some(count) = app.pending_count {
+        spans.push(ratatui::text::Span::raw(" | "));
+        spans.push(ratatui::text::Span::styled(
+            count.to_string(),
+            app.theme.status,
+        ));
+    }
+
+    spans.push(ratatui::text::Span::raw(" | "));
+    spans.push(ratatui::text::Span::styled(help, app.theme.hint));
+    let status_line = Line::from(spans);
 
     let block = Block::default().borders(Borders::ALL);
     let paragraph = Paragraph::new(status_line).block(block);
     frame.render_widget(paragraph, area);
 }
 
-fn numbered_items(titles: &[String], selected: Option<usize>) -> Vec<ListItem<'static>> {
+// What the status bar offers in each mode; keys come from the keymap
+const fn help_entries(app: &App) -> &'static [(&'static [Action], &'static str)] {
+    match app.overlay {
+        Overlay::Help => &[
+            (&[Action::Cancel], "close"),
+            (&[Action::MoveDown, Action::MoveUp], "scroll"),
+        ],
+        Overlay::Search => &[
+            (&[Action::Cancel], "cancel"),
+            (&[Action::Submit], "finish"),
+            (&[Action::HistoryPrev, Action::HistoryNext], "history"),
+            (&[Action::DeleteWord, Action::ClearLine], "delete word/line"),
+        ],
+        Overlay::CourseSearch | Overlay::LessonFinder => &[
+            (&[Action::Cancel], "close"),
+            (&[Action::Submit], "open"),
+            (&[Action::MoveUp, Action::MoveDown], "select"),
+        ],
+        Overlay::Toc => &[
+            (&[Action::Quit], "quit"),
+            (&[Action::Cancel], "close"),
+            (&[Action::MoveDown, Action::MoveUp], "down/up"),
+            (&[Action::Select], "jump"),
+        ],
+        Overlay::None => match app.view {
+            View::CourseList => &[
+                (&[Action::Quit], "quit"),
+                (&[Action::Help], "help"),
+                (&[Action::Search], "search"),
+                (&[Action::MoveDown, Action::MoveUp], "down/up"),
+                (&[Action::Select], "select"),
+            ],
+            View::CourseContent => match app.active_pane {
+                Pane::Chapters | Pane::Lessons => &[
+                    (&[Action::Quit], "quit"),
+                    (&[Action::Help], "help"),
+                    (&[Action::BackToCourses], "courses"),
+                    (&[Action::GoBack, Action::Select], "back/forward"),
+                    (&[Action::MoveDown, Action::MoveUp], "down/up"),
+                    (&[Action::Search], "filter"),
+                    (&[Action::SearchCourse], "search course"),
+                    (&[Action::FindLesson], "go to lesson"),
+                    (&[Action::ReadChapter], "read chapter"),
+                ],
+                Pane::Readme => &[
+                    (&[Action::Quit], "quit"),
+                    (&[Action::Help], "help"),
+                    (&[Action::BackToCourses], "courses"),
+                    (&[Action::GoBack], "back"),
+                    (&[Action::MoveDown, Action::MoveUp], "scroll"),
+                    (&[Action::Search], "search"),
+                    (&[Action::NextMatch, Action::PrevMatch], "next/prev"),
+                    (
+                        &[Action::NextLesson, Action::PrevLesson],
+                        "next/prev lesson",
+                    ),
+                    (&[Action::Toc], "contents"),
+                    (&[Action::ToggleZen], "fullscreen"),
+                    (&[Action::SearchCourse], "search course"),
+                    (&[Action::FindLesson], "go to lesson"),
+                ],
+            },
+        },
+    }
+}
+
+fn numbered_items(
+    titles: &[String],
+    selected: Option<usize>,
+    selected_style: Style,
+) -> Vec<ListItem<'static>> {
     titles
         .iter()
         .enumerate()
@@ -216,18 +732,54 @@ fn numbered_items(titles: &[String], selected: Option<usize>) -> Vec<ListItem<'s
             let mut item = ListItem::new(format!("{}. {}", i + 1, title));
             let item_no = i + 1;
             if Some(item_no) == selected {
-                item = item.style(Style::default().fg(Color::Green));
+                item = item.style(selected_style);
             }
             item
         })
         .collect()
 }
 
+// Just the numbers, for a collapsed pane
+fn number_items(
+    len: usize,
+    selected: Option<usize>,
+    selected_style: Style,
+) -> Vec<ListItem<'static>> {
+    (1..=len)
+        .map(|item_no| {
+            let item = ListItem::new(item_no.to_string());
+            if Some(item_no) == selected {
+                item.style(selected_style)
+            } else {
+                item
+            }
+        })
+        .collect()
+}
+
+// Only the items at the given (sorted) indices
+fn keep_items<'a>(items: Vec<ListItem<'a>>, indices: &[usize]) -> Vec<ListItem<'a>> {
+    items
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| indices.binary_search(i).is_ok())
+        .map(|(_, item)| item)
+        .collect()
+}
+
+fn filtered_title(title: &str, filter: &str) -> String {
+    if filter.is_empty() {
+        title.to_owned()
+    } else {
+        format!("{} /{}", title, filter)
+    }
+}
+
 fn items_from_highlighted<'a>(
     highlighted: &'a str,
     fallback: Vec<ListItem<'a>>,
     selected: Option<usize>,
-    selected_color: Color,
+    selected_style: Style,
 ) -> Vec<ListItem<'a>> {
     if highlighted.is_empty() {
         return fallback;
@@ -244,7 +796,7 @@ fn items_from_highlighted<'a>(
                     let line_no = i + 1;
                     if Some(line_no) == selected {
                         for span in &mut line.spans {
-                            span.style = span.style.fg(selected_color);
+                            span.style = span.style.patch(selected_style);
                         }
                     }
                     ListItem::new(line)
@@ -254,24 +806,63 @@ fn items_from_highlighted<'a>(
     )
 }
 
-fn make_block(title: &str, is_active: bool) -> Block<'_> {
-    let border_style = if is_active {
-        Style::default().fg(Color::Cyan)
-    } else {
-        Style::default().fg(Color::DarkGray)
-    };
+// Split text into spans, styling the chars at the given indices
+fn highlight_chars(text: &str, indices: &[usize], style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let matched = indices.contains(&i);
+        if matched != current_matched && !current.is_empty() {
+            let content = std::mem::take(&mut current);
+            spans.push(if current_matched {
+                Span::styled(content, style)
+            } else {
+                Span::raw(content)
+            });
+        }
+        current_matched = matched;
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        spans.push(if current_matched {
+            Span::styled(current, style)
+        } else {
+            Span::raw(current)
+        });
+    }
 
-    let title_style = if is_active {
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD)
+    spans
+}
+
+fn make_block<'a>(title: &'a str, is_active: bool, theme: &Theme) -> Block<'a> {
+    let (border_style, border_type, title_style) = if is_active {
+        (
+            theme.border_active,
+            theme.border_type_active,
+            theme.title_active,
+        )
     } else {
-        Style::default().fg(Color::White)
+        (theme.border, theme.border_type, theme.title)
     };
 
     Block::default()
         .title(format!(" {} ", title))
         .title_style(title_style)
         .borders(Borders::ALL)
+        .border_type(border_type)
         .border_style(border_style)
 }
+
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,135 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+// Lines starting with this are centered in the width they're wrapped to (used
+// for display math, whose width isn't known until the readme is drawn)
+pub const CENTER_MARK: char = '\u{2063}';
+
+// Extra style applied to a char range of a source line (e.g., search matches)
+#[derive(Debug, Clone, Copy)]
+pub struct Highlight {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub style: Style,
+}
+
+pub struct Wrapped {
+    pub lines: Vec<Line<'static>>,
+    pub rows: Vec<(usize, usize)>, // (source line, first char) of each row
+}
+
+/// Word-wrap lines to `width` columns, so that we know where every source
+/// char ends up on screen
+#[must_use]
+pub fn wrap_lines(lines: &[Line], width: usize, highlights: &[Highlight]) -> Wrapped {
+    let width = width.max(1);
+    let mut wrapped = Wrapped {
+        lines: Vec::new(),
+        rows: Vec::new(),
+    };
+
+    for (line_no, line) in lines.iter().enumerate() {
+        let mut chars: Vec<(char, Style)> = line
+            .spans
+            .iter()
+            .flat_map(|span| {
+                let style = line.style.patch(span.style);
+                span.content.chars().map(move |c| (c, style))
+            })
+            .collect();
+
+        for hl in highlights.iter().filter(|hl| hl.line == line_no) {
+            for (_, style) in chars.iter_mut().take(hl.end).skip(hl.start) {
+                *style = style.patch(hl.style);
+            }
+        }
+
+        // The mark keeps its char index, but isn't drawn
+        let is_centered = chars.first().is_some_and(|&(c, _)| c == CENTER_MARK);
+        let skip = usize::from(is_centered);
+        let text = &chars[skip..];
+
+        for (start, end) in break_points(text, width) {
+            let mut line = to_line(&text[start..end]);
+            if is_centered {
+                let row_width: usize = text[start..end]
+                    .iter()
+                    .map(|&(c, _)| c.width().unwrap_or(0))
+                    .sum();
+                let padding = " ".repeat(width.saturating_sub(row_width) / 2);
+                line.spans.insert(0, Span::raw(padding));
+            }
+            wrapped.lines.push(line);
+            let first = if start == 0 { 0 } else { start + skip };
+            wrapped.rows.push((line_no, first));
+        }
+    }
+
+    wrapped
+}
+
+fn break_points(chars: &[(char, Style)], width: usize) -> Vec<(usize, usize)> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = start;
+        let mut row_width = 0;
+        let mut last_break = None;
+
+        while end < chars.len() {
+            let (c, _) = chars[end];
+            let char_width = c.width().unwrap_or(0);
+            if row_width + char_width > width && end > start {
+                break;
+            }
+            row_width += char_width;
+            end += 1;
+            if c.is_whitespace() {
+                last_break = Some(end);
+            }
+        }
+
+        // Prefer breaking after whitespace; hard-break words that don't fit
+        if end < chars.len()
+            && let Some(b) = last_break
+        {
+            end = b;
+        }
+
+        rows.push((start, end));
+        start = end;
+    }
+
+    if rows.is_empty() {
+        rows.push((0, 0));
+    }
+
+    rows
+}
+
+fn to_line(chars: &[(char, Style)]) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut content = String::new();
+    let mut current = None;
+
+    for &(c, style) in chars {
+        if let Some(prev) = current
+            && prev != style
+        {
+            spans.push(Span::styled(std::mem::take(&mut content), prev));
+        }
+        current = Some(style);
+        content.push(c);
+    }
+
+    if let Some(style) = current {
+        spans.push(Span::styled(content, style));
+    }
+
+    Line::from(spans)
+}